@@ -1,11 +1,17 @@
 #define _GNU_SOURCE
 
+#include "ctype.h"
+#include "errno.h"
+#include "limits.h"
+#include "math.h"
+#include "stdarg.h"
+#include "stdbool.h"
 #include "stdio.h"
 #include "stdlib.h"
 #include "string.h"
 
-void __func__error();
-void __func__printString(const char*);
+void __builtin_func__error();
+void __builtin_func__printString(const char*);
 
 /**
  * initialize empty string with a given size
@@ -15,7 +21,7 @@ void __func__printString(const char*);
 char* __builtin_method__str__init__(int size) {
     // allocate memory (+1) to contain 0 at the end
     char* s = (char *)malloc(size + 1);
-    if (s == NULL) __func__error();
+    if (s == NULL) __builtin_func__error();
 
     // fill memory with zeros
     memset(s, (char)0, size+1);
@@ -41,9 +47,66 @@ char* __builtin_method__str__concat__(char* left, char* right) {
     return combined;
 }
 
+/**
+ * build interpolated string literal, format is created by the compiler
+ * based on types of the interpolated values
+ * @param format - printf-style format string
+ * @return - formatted string
+ */
+char* __builtin_method__str__format__(const char* format, ...) {
+    va_list args, args_copy;
+    va_start(args, format);
+    va_copy(args_copy, args);
+
+    // first pass only calculates the length of the result
+    int len = vsnprintf(NULL, 0, format, args);
+    char* formatted = __builtin_method__str__init__(len);
+    vsnprintf(formatted, len + 1, format, args_copy);
+
+    va_end(args_copy);
+    va_end(args);
+    return formatted;
+}
+
+/**
+ * compare strings by value (used by switch and comparison operators),
+ * null is equal only to itself and lower than any other string
+ * @param left - string
+ * @param right - string
+ * @return - negative, zero or positive value, like strcmp
+ */
+int __builtin_method__str__compare__(char* left, char* right) {
+    if (left == right) return 0;
+    if (left == NULL) return -1;
+    if (right == NULL) return 1;
+    return strcmp(left, right);
+}
+
+/**
+ * length of the string (string.length), null (e.g. uninitialized field) has no characters
+ * @param str - string
+ * @return - number of characters
+ */
+int __builtin_method__str__length__(char* str) {
+    if (str == NULL) return 0;
+    return strlen(str);
+}
+
+/**
+ * copy part of the string to a new string
+ * @param str - string
+ * @param len - number of characters to copy
+ * @return - new string
+ */
+char* __builtin_method__str__copy__(const char* str, int len) {
+    char* copy = __builtin_method__str__init__(len);
+    memcpy(copy, str, len);
+    return copy;
+}
+
 void* __builtin_method__array__init__(int size) {
     void* arr = malloc(size);
-    if (arr == NULL) __func__error();
+    if (arr == NULL) __builtin_func__error();
 
     // fill memory with zeros
     memset(arr, (char)0, size+1);
@@ -51,29 +114,40 @@ void* __builtin_method__array__init__(int size) {
 }
 
 /// latte standard library
-void __func__printInt(int i) {
+void __builtin_func__printInt(int i) {
     printf("%d\n", i);
 }
 
 /// latte standard library
-void __func__printString(const char* str) {
+void __builtin_func__printString(const char* str) {
     printf("%s\n", str);
 }
 
+/**
+ * print values using format created by the compiler (print, println and printf builtins)
+ * @param format - printf-style format string
+ */
+void __builtin_method__print__(const char* format, ...) {
+    va_list args;
+    va_start(args, format);
+    vprintf(format, args);
+    va_end(args);
+}
+
 /// latte standard library
-int __func__readInt() {
+int __builtin_func__readInt() {
     int i;
     scanf("%d\n", &i);
     return i;
 }
 
 /// latte standard library
-char* __func__readString() {
+char* __builtin_func__readString() {
     // getline will allocate memory if c = NULL and n = 0
     char* c = NULL;
     size_t n = 0;
     ssize_t r = getline(&c, &n, stdin);
-    if (r < 0) __func__error();
+    if (r < 0) __builtin_func__error();
 
     // getline returns a '\n' unless the EOF was reached before it, we need to clear that from the string
     if (c[r-1] == '\n') c[r-1] = (char)0;
@@ -81,7 +155,518 @@ char* __func__readString() {
 }
 
 /// latte standard library
-void __func__error() {
-    __func__printString("runtime error");
+void __builtin_func__printChar(char c) {
+    printf("%c\n", c);
+}
+
+/// latte standard library
+int __builtin_func__charToInt(char c) {
+    return c;
+}
+
+/// latte standard library
+char __builtin_func__intToChar(int i) {
+    return (char)i;
+}
+
+/// latte standard library
+void __builtin_func__printLong(long long l) {
+    printf("%lld\n", l);
+}
+
+/// latte standard library
+long long __builtin_func__readLong() {
+    long long l;
+    scanf("%lld\n", &l);
+    return l;
+}
+
+/// latte standard library
+long long __builtin_func__intToLong(int i) {
+    return i;
+}
+
+/// latte standard library
+int __builtin_func__longToInt(long long l) {
+    // keep only the lowest 32 bits
+    return (int)l;
+}
+
+/// latte standard library
+void __builtin_func__printDouble(double d) {
+    printf("%.15g\n", d);
+}
+
+/// latte standard library
+double __builtin_func__readDouble() {
+    double d;
+    scanf("%lf\n", &d);
+    return d;
+}
+
+/// latte standard library
+double __builtin_func__intToDouble(int i) {
+    return i;
+}
+
+/// latte standard library
+int __builtin_func__doubleToInt(double d) {
+    // truncate towards zero
+    return (int)d;
+}
+
+/// latte standard library
+double __builtin_func__sqrt(double d) {
+    return sqrt(d);
+}
+
+/// latte standard library
+double __builtin_func__floor(double d) {
+    return floor(d);
+}
+
+/// latte standard library
+double __builtin_func__pow(double base, double exponent) {
+    return pow(base, exponent);
+}
+
+/// latte standard library
+void __builtin_func__error() {
+    __builtin_func__printString("runtime error");
+    exit(1);
+}
+
+/// struct used by the compiler to represent string[]
+typedef struct {
+    int length;
+    char** array;
+} __builtin_struct__array_str;
+
+/// latte standard library
+char __builtin_func__charAt(char* str, int idx) {
+    if (str == NULL || idx < 0 || idx >= (int)strlen(str)) __builtin_func__error();
+    return str[idx];
+}
+
+/// latte standard library
+char* __builtin_func__substring(char* str, int begin, int end) {
+    if (begin < 0 || end > (int)strlen(str) || begin > end) __builtin_func__error();
+    return __builtin_method__str__copy__(str + begin, end - begin);
+}
+
+/// latte standard library
+int __builtin_func__indexOf(char* str, char* sub) {
+    char* found = strstr(str, sub);
+    return found == NULL ? -1 : (int)(found - str);
+}
+
+/// latte standard library
+bool __builtin_func__startsWith(char* str, char* prefix) {
+    return strncmp(str, prefix, strlen(prefix)) == 0;
+}
+
+/// latte standard library
+__builtin_struct__array_str* __builtin_func__split(char* str, char* sep) {
+    size_t sep_len = strlen(sep);
+    if (sep_len == 0) __builtin_func__error();
+
+    // count parts first, there is always one more part than separators
+    int count = 1;
+    for (char* found = strstr(str, sep); found != NULL; found = strstr(found + sep_len, sep)) {
+        count++;
+    }
+
+    __builtin_struct__array_str* result = __builtin_method__array__init__(sizeof(__builtin_struct__array_str));
+    result->length = count;
+    result->array = __builtin_method__array__init__(count * sizeof(char*));
+
+    char* begin = str;
+    for (int i = 0; i < count - 1; i++) {
+        char* found = strstr(begin, sep);
+        result->array[i] = __builtin_method__str__copy__(begin, found - begin);
+        begin = found + sep_len;
+    }
+    result->array[count - 1] = __builtin_method__str__copy__(begin, strlen(begin));
+    return result;
+}
+
+/// latte standard library
+char* __builtin_func__intToString(int i) {
+    // 11 characters are enough for any 32-bit integer with sign
+    char* str = __builtin_method__str__init__(11);
+    sprintf(str, "%d", i);
+    return str;
+}
+
+/// latte standard library
+int __builtin_func__stringToInt(char* str) {
+    char* end;
+    errno = 0;
+    long val = strtol(str, &end, 10);
+    if (end == str || *end != '\0') __builtin_func__error();
+    // values which do not fit in int are not truncated
+    if (errno == ERANGE || val < INT_MIN || val > INT_MAX) __builtin_func__error();
+    return (int)val;
+}
+
+/// latte standard library
+char* __builtin_func__trim(char* str) {
+    char* begin = str;
+    while (isspace((unsigned char)*begin)) begin++;
+    char* end = begin + strlen(begin);
+    while (end > begin && isspace((unsigned char)*(end - 1))) end--;
+    return __builtin_method__str__copy__(begin, end - begin);
+}
+
+/// growable list, items of any type are stored in a buffer that doubles when it is full
+typedef struct {
+    int size;
+    int capacity;
+    int item_size;
+    char* items;
+} __builtin_struct__list;
+
+/**
+ * initialize empty list
+ * @param item_size - size of a single item (in bytes)
+ * @return list
+ */
+__builtin_struct__list* __builtin_method__list__init__(int item_size) {
+    __builtin_struct__list* list = __builtin_method__array__init__(sizeof(__builtin_struct__list));
+    list->size = 0;
+    list->capacity = 8;
+    list->item_size = item_size;
+    list->items = __builtin_method__array__init__(list->capacity * item_size);
+    return list;
+}
+
+/**
+ * number of items in the list (list.size())
+ * @param list - list
+ * @return - number of items
+ */
+int __builtin_method__list__size__(__builtin_struct__list* list) {
+    if (list == NULL) __builtin_func__error();
+    return list->size;
+}
+
+/**
+ * append a slot for a new item at the end of the list (list.push(x)),
+ * the caller stores the item in the returned slot
+ * @param list - list
+ * @return - pointer to the new slot
+ */
+char* __builtin_method__list__push__(__builtin_struct__list* list) {
+    if (list == NULL) __builtin_func__error();
+    if (list->size == list->capacity) {
+        list->capacity *= 2;
+        list->items = realloc(list->items, list->capacity * list->item_size);
+        if (list->items == NULL) __builtin_func__error();
+    }
+    return list->items + list->size++ * list->item_size;
+}
+
+/**
+ * remove the last item of the list (list.pop())
+ * @param list - list
+ * @return - pointer to the removed item, valid until the list is modified
+ */
+char* __builtin_method__list__pop__(__builtin_struct__list* list) {
+    if (list == NULL || list->size == 0) __builtin_func__error();
+    return list->items + --list->size * list->item_size;
+}
+
+/**
+ * access item of the list (list.get(i) and list.set(i, x))
+ * @param list - list
+ * @param idx - index of the item
+ * @return - pointer to the item
+ */
+char* __builtin_method__list__at__(__builtin_struct__list* list, int idx) {
+    if (list == NULL || idx < 0 || idx >= list->size) __builtin_func__error();
+    return list->items + idx * list->item_size;
+}
+
+/**
+ * insert a slot for a new item before the given index (list.insert(i, x)),
+ * the caller stores the item in the returned slot
+ * @param list - list
+ * @param idx - index of the new item, at most list size
+ * @return - pointer to the new slot
+ */
+char* __builtin_method__list__insert__(__builtin_struct__list* list, int idx) {
+    if (list == NULL || idx < 0 || idx > list->size) __builtin_func__error();
+    __builtin_method__list__push__(list);
+    char* slot = list->items + idx * list->item_size;
+    memmove(slot + list->item_size, slot, (list->size - 1 - idx) * list->item_size);
+    return slot;
+}
+
+/**
+ * remove item at the given index (list.remove(i)), the removed item is moved
+ * to the unused slot right after the last item
+ * @param list - list
+ * @param idx - index of the removed item
+ * @return - pointer to the removed item, valid until the list is modified
+ */
+char* __builtin_method__list__remove__(__builtin_struct__list* list, int idx) {
+    char* slot = __builtin_method__list__at__(list, idx);
+    char removed[list->item_size];
+    memcpy(removed, slot, list->item_size);
+    memmove(slot, slot + list->item_size, (list->size - 1 - idx) * list->item_size);
+    char* last = __builtin_method__list__pop__(list);
+    memcpy(last, removed, list->item_size);
+    return last;
+}
+
+/// key of a map entry, maps have either int or string keys
+typedef union {
+    int i;
+    char* s;
+} __builtin_map_key;
+
+typedef struct {
+    __builtin_map_key key;
+    unsigned hash;
+    bool removed;
+} __builtin_map_entry;
+
+/// hash map, entries are kept in insertion order and indexed by an open addressing table
+typedef struct {
+    int size;
+    int count;
+    int capacity;
+    bool string_keys;
+    int value_size;
+    __builtin_map_entry* entries;
+    char* values;
+    int* buckets;
+} __builtin_struct__map;
+
+static unsigned __builtin_map_hash(__builtin_struct__map* map, __builtin_map_key key) {
+    if (!map->string_keys) return (unsigned)key.i * 2654435761u;
+    // FNV-1a
+    unsigned hash = 2166136261u;
+    for (char* c = key.s; *c != '\0'; c++) {
+        hash = (hash ^ (unsigned char)*c) * 16777619u;
+    }
+    return hash;
+}
+
+static bool __builtin_map_key_equal(__builtin_struct__map* map, __builtin_map_key a, __builtin_map_key b) {
+    return map->string_keys ? strcmp(a.s, b.s) == 0 : a.i == b.i;
+}
+
+/// rebuild the index with buckets for twice as many entries as the capacity
+static void __builtin_map_reindex(__builtin_struct__map* map) {
+    int bucket_count = 2 * map->capacity;
+    free(map->buckets);
+    map->buckets = malloc(bucket_count * sizeof(int));
+    if (map->buckets == NULL) __builtin_func__error();
+    memset(map->buckets, -1, bucket_count * sizeof(int));
+    for (int i = 0; i < map->count; i++) {
+        unsigned b = map->entries[i].hash & (bucket_count - 1);
+        while (map->buckets[b] != -1) b = (b + 1) & (bucket_count - 1);
+        map->buckets[b] = i;
+    }
+}
+
+/**
+ * find index of the entry with the given key
+ * @return - index of the entry or -1 if the key is not in the map
+ */
+static int __builtin_map_find(__builtin_struct__map* map, __builtin_map_key key, unsigned hash) {
+    int bucket_count = 2 * map->capacity;
+    for (unsigned b = hash & (bucket_count - 1); map->buckets[b] != -1; b = (b + 1) & (bucket_count - 1)) {
+        __builtin_map_entry* entry = &map->entries[map->buckets[b]];
+        if (!entry->removed && entry->hash == hash && __builtin_map_key_equal(map, entry->key, key)) {
+            return map->buckets[b];
+        }
+    }
+    return -1;
+}
+
+/**
+ * initialize empty map
+ * @param string_keys - whether keys are strings (otherwise ints)
+ * @param value_size - size of a single value (in bytes)
+ * @return map
+ */
+__builtin_struct__map* __builtin_method__map__init__(bool string_keys, int value_size) {
+    __builtin_struct__map* map = __builtin_method__array__init__(sizeof(__builtin_struct__map));
+    map->size = 0;
+    map->count = 0;
+    map->capacity = 8;
+    map->string_keys = string_keys;
+    map->value_size = value_size;
+    map->entries = __builtin_method__array__init__(map->capacity * sizeof(__builtin_map_entry));
+    map->values = __builtin_method__array__init__(map->capacity * value_size);
+    map->buckets = NULL;
+    __builtin_map_reindex(map);
+    return map;
+}
+
+/**
+ * number of keys in the map (map.size())
+ * @param map - map
+ * @return - number of keys
+ */
+int __builtin_method__map__size__(__builtin_struct__map* map) {
+    if (map == NULL) __builtin_func__error();
+    return map->size;
+}
+
+/**
+ * get value slot for the key, inserting a new entry if necessary (map.put(k, v)),
+ * the caller stores the value in the returned slot
+ */
+static char* __builtin_map_put(__builtin_struct__map* map, __builtin_map_key key) {
+    if (map == NULL) __builtin_func__error();
+    unsigned hash = __builtin_map_hash(map, key);
+    int idx = __builtin_map_find(map, key, hash);
+    if (idx != -1) return map->values + idx * map->value_size;
+
+    if (map->count == map->capacity) {
+        // drop removed entries, grow only if the map is at least half full
+        int live = 0;
+        for (int i = 0; i < map->count; i++) {
+            if (map->entries[i].removed) continue;
+            map->entries[live] = map->entries[i];
+            memmove(map->values + live * map->value_size, map->values + i * map->value_size, map->value_size);
+            live++;
+        }
+        map->count = live;
+        if (2 * live >= map->capacity) {
+            map->capacity *= 2;
+            map->entries = realloc(map->entries, map->capacity * sizeof(__builtin_map_entry));
+            map->values = realloc(map->values, map->capacity * map->value_size);
+            if (map->entries == NULL || map->values == NULL) __builtin_func__error();
+        }
+        __builtin_map_reindex(map);
+    }
+
+    idx = map->count++;
+    map->entries[idx].key = key;
+    map->entries[idx].hash = hash;
+    map->entries[idx].removed = false;
+    map->size++;
+
+    int bucket_count = 2 * map->capacity;
+    unsigned b = hash & (bucket_count - 1);
+    while (map->buckets[b] != -1) b = (b + 1) & (bucket_count - 1);
+    map->buckets[b] = idx;
+
+    char* slot = map->values + idx * map->value_size;
+    memset(slot, 0, map->value_size);
+    return slot;
+}
+
+/// get value slot for the key (map.get(k)), missing key is a runtime error
+static char* __builtin_map_get(__builtin_struct__map* map, __builtin_map_key key) {
+    if (map == NULL) __builtin_func__error();
+    int idx = __builtin_map_find(map, key, __builtin_map_hash(map, key));
+    if (idx == -1) __builtin_func__error();
+    return map->values + idx * map->value_size;
+}
+
+/// check if the key is in the map (map.containsKey(k))
+static bool __builtin_map_contains(__builtin_struct__map* map, __builtin_map_key key) {
+    if (map == NULL) __builtin_func__error();
+    return __builtin_map_find(map, key, __builtin_map_hash(map, key)) != -1;
+}
+
+/// remove the key from the map if it is present (map.remove(k))
+static void __builtin_map_remove(__builtin_struct__map* map, __builtin_map_key key) {
+    if (map == NULL) __builtin_func__error();
+    int idx = __builtin_map_find(map, key, __builtin_map_hash(map, key));
+    if (idx == -1) return;
+    map->entries[idx].removed = true;
+    map->size--;
+}
+
+/**
+ * list of keys in the order of insertion (map.keys(), iteration over a map)
+ * @param map - map
+ * @return - new list containing the keys
+ */
+__builtin_struct__list* __builtin_method__map__keys__(__builtin_struct__map* map) {
+    if (map == NULL) __builtin_func__error();
+    int key_size = map->string_keys ? sizeof(char*) : sizeof(int);
+    __builtin_struct__list* keys = __builtin_method__list__init__(key_size);
+    for (int i = 0; i < map->count; i++) {
+        if (map->entries[i].removed) continue;
+        memcpy(__builtin_method__list__push__(keys), &map->entries[i].key, key_size);
+    }
+    return keys;
+}
+
+/// map methods for int keys
+char* __builtin_method__map_int__put__(__builtin_struct__map* map, int key) {
+    return __builtin_map_put(map, (__builtin_map_key){ .i = key });
+}
+
+char* __builtin_method__map_int__get__(__builtin_struct__map* map, int key) {
+    return __builtin_map_get(map, (__builtin_map_key){ .i = key });
+}
+
+bool __builtin_method__map_int__containsKey__(__builtin_struct__map* map, int key) {
+    return __builtin_map_contains(map, (__builtin_map_key){ .i = key });
+}
+
+void __builtin_method__map_int__remove__(__builtin_struct__map* map, int key) {
+    __builtin_map_remove(map, (__builtin_map_key){ .i = key });
+}
+
+/// map methods for string keys, keys are compared by value
+char* __builtin_method__map_str__put__(__builtin_struct__map* map, char* key) {
+    if (key == NULL) __builtin_func__error();
+    return __builtin_map_put(map, (__builtin_map_key){ .s = key });
+}
+
+char* __builtin_method__map_str__get__(__builtin_struct__map* map, char* key) {
+    if (key == NULL) __builtin_func__error();
+    return __builtin_map_get(map, (__builtin_map_key){ .s = key });
+}
+
+bool __builtin_method__map_str__containsKey__(__builtin_struct__map* map, char* key) {
+    if (key == NULL) __builtin_func__error();
+    return __builtin_map_contains(map, (__builtin_map_key){ .s = key });
+}
+
+void __builtin_method__map_str__remove__(__builtin_struct__map* map, char* key) {
+    if (key == NULL) __builtin_func__error();
+    __builtin_map_remove(map, (__builtin_map_key){ .s = key });
+}
+
+/// exception that is currently propagated, compiled code checks it after every call
+static void* __builtin_exception = NULL;
+
+void __builtin_method__exception__throw__(void* exception) {
+    if (exception == NULL) __builtin_func__error();
+    __builtin_exception = exception;
+}
+
+void* __builtin_method__exception__get__() {
+    return __builtin_exception;
+}
+
+void __builtin_method__exception__clear__() {
+    __builtin_exception = NULL;
+}
+
+/// exception propagated out of main terminates the program, like the error builtin
+void __builtin_method__exception__uncaught__() {
+    __builtin_func__printString("uncaught exception");
     exit(1);
 }
+
+/// failed assert statement, location (file:line:column) is known at compile time, message is optional
+void __builtin_method__assert__fail__(const char* location, const char* message) {
+    if (message == NULL) {
+        fprintf(stderr, "%s: assertion failed\n", location);
+    } else {
+        fprintf(stderr, "%s: assertion failed: %s\n", location, message);
+    }
+    // status different than the one used by the error builtin
+    exit(3);
+}
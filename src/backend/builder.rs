@@ -1,50 +1,31 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::collections::hash_map::RandomState;
 
-use crate::backend::context::FunctionContext;
 use crate::backend::ir::{BasicBlock, Entity, GetEntity, Instruction, InstructionKind};
-use crate::frontend::ast::Type;
-use crate::meta::{GetType, Meta};
-use crate::util::env::Env;
 use regex::internal::Inst;
 
 #[derive(Debug, Clone)]
 pub struct BlockBuilder {
     /// currently built basic block
     block: BasicBlock,
-
-    /// entity mapping, available to re-use after initial block build
-    entity_mapping: Option<HashMap<Entity, Entity>>,
-
-    /// predecessor label => predecessor variable ident => entity in that predecessor
-    predecessors: Env<Env<Entity>>,
-
-    /// variables to which values were assigned in the block
-    variables_gen: Env<Entity>,
 }
 
 impl BlockBuilder {
     pub fn without_label() -> Self {
         Self {
-            predecessors: Env::new(),
             block: BasicBlock {
                 label: None,
                 instructions: vec![],
             },
-            entity_mapping: None,
-            variables_gen: Env::new(),
         }
     }
 
     pub fn with_label(label: String) -> Self {
         Self {
-            predecessors: Env::new(),
             block: BasicBlock {
                 label: Some(label),
                 instructions: vec![],
             },
-            entity_mapping: None,
-            variables_gen: Env::new(),
         }
     }
 
@@ -52,122 +33,25 @@ impl BlockBuilder {
         self.block.get_label()
     }
 
-    /// register environment from a compiled predecessor
-    pub fn add_predecessor(&mut self, label: String, env: &Env<Entity>) {
-        self.predecessors.insert(label, env.clone());
-    }
-
     /// add new instruction to the current basic block
     pub fn push_instruction(&mut self, instr: Instruction) {
         self.block.instructions.push(instr)
     }
 
-    /// checks if current block always returns
-    pub fn block_always_returns(&self) -> bool {
-        self.block.always_returns()
+    /// checks if current block is already terminated (by return or jump), so it cannot be continued
+    pub fn block_is_terminated(&self) -> bool {
+        self.block.is_terminated()
     }
 
-    /// build the block, adding all necessary phi instructions at the beginning
-    pub fn build(&mut self, function_ctx: &mut FunctionContext) -> BasicBlock {
-        // create mapping: variable => (vec![(predecessor_label, entity_in_predecessor)], set of unique entities)
-        // TODO: Refactor needed, this code is highly unreadable
-        // TODO: Tests needed
-        if self.predecessors.len() < 2 {
-            return self.block.clone();
-        }
-
-        let mut predecessor_key_locations: Env<(Vec<(Entity, String)>, HashSet<Entity>)> = Env::new();
-
-        for (predecessor_name, predecessor_env) in self.predecessors.iter() {
-            for (key, entity) in predecessor_env.iter() {
-                let location = (entity.clone(), predecessor_name.clone());
-                if let Some(predecessor_data) = &mut predecessor_key_locations.get(key) {
-                    // variable already existed in one of the previous predecessors' envs
-                    let mut locations_vec = predecessor_data.0.clone();
-                    let mut unique_entities = predecessor_data.1.clone();
-                    locations_vec.push(location);
-                    unique_entities.insert(entity.clone());
-                    predecessor_key_locations.insert(key.clone(), (locations_vec, unique_entities));
-                } else {
-                    // variable appears for the first time
-                    let mut new_set = HashSet::new();
-                    new_set.insert(entity.clone());
-                    predecessor_key_locations.insert(key.clone(), (vec![location], new_set));
-                }
-            }
-        }
-
-        // variable identifiers for which name conflicts are possible
-        let phi_keys: HashSet<_> = predecessor_key_locations.iter()
-            .filter(|(_, (_, unique_ents))| unique_ents.len() >= 2)
-            .map(|(key, _)| key.clone())
-            .collect();
-
-        // mutable block is necessary for swapping instruction entities
-        let mut cyclic_shift = 0 as usize;
-        let mut phi_instructions = Vec::new();
-        let mut entity_mapping = HashMap::new();
-
-        for key in phi_keys {
-            // create phi instruction
-            let variable_t = predecessor_key_locations.get(&key).unwrap().0[0].0.get_type();
-            let phi_instr = InstructionKind::Phi {
-                args: predecessor_key_locations.get(&key).unwrap().0.clone()
-            };
-
-            let available_register = function_ctx.new_register(variable_t.clone());
-
-            let actual_phi_register = if let Some(Entity::Register { n, t: _ }) = self.block.get_first_register() {
-                // actual phi register will have the number of the first original register in the block,
-                // and we'll shift the original registers' numbers later to prevent conflicts
-                Entity::Register {
-                    n: n + cyclic_shift,
-                    t: variable_t,
-                }
-            } else {
-                // instructions in the don't use result registers so we use the available register
-                available_register
-            };
-            phi_instructions.push(phi_instr.with_result(actual_phi_register.clone()));
-
-            // the change is stored in mapping, that will later allow for re-numbering of arguments
-            for original_env_location_data in &predecessor_key_locations.get(&key).unwrap().0 {
-                let original_env_location = original_env_location_data.0.clone();
-                entity_mapping.insert(original_env_location, actual_phi_register.clone());
-            }
-            cyclic_shift += 1;
-        }
-
-        let increment_from_value = if let Some(Entity::Register { n, t: _ }) = self.block.get_first_register() {
-            n
-        } else {
-            0
-        };
-        let increment_mapper = IncrementMapper {
-            offset: cyclic_shift,
-            from_value: increment_from_value
-        };
-
-        // re-number original block statements and append them after newly created phi instructions
-        let mut block_instructions = self.block.instructions.iter()
-            .map(|i| i.map_entities(&increment_mapper, &entity_mapping))
-            .collect();
-        phi_instructions.append(&mut block_instructions);
-        self.block.instructions = phi_instructions;
-
-        // save entity mapping for easy re-use
-        self.entity_mapping = Some(entity_mapping);
-
-        self.block.clone()
+    /// map entities in instructions of the current block using provided mapping
+    pub fn map_entities(&mut self, mapping: &HashMap<Entity, Entity>) {
+        let increment_mapper = IncrementMapper { offset: 0, from_value: 0 };
+        self.block = self.block.map_entities(&increment_mapper, mapping);
     }
 
-    /// get entity mapping from already built block, that can later be applied to another block
-    pub fn get_entity_mapping(&mut self, function_ctx: &mut FunctionContext) -> &HashMap<Entity, Entity> {
-        if let Some(mapping) = &self.entity_mapping {
-            mapping
-        } else {
-            panic!("to get mapping, build the block first")
-        }
+    /// build the block
+    pub fn build(&mut self) -> BasicBlock {
+        self.block.clone()
     }
 }
 
@@ -250,6 +134,13 @@ impl MapEntities for Instruction {
                     args: args.iter().map(|arg| arg.map_entities(increment_mapper, direct_mapping)).collect(),
                 }
             }
+            InstructionKind::CallVariadic { func, fixed_args, args } => {
+                InstructionKind::CallVariadic {
+                    func: func.clone(),
+                    fixed_args: *fixed_args,
+                    args: args.iter().map(|arg| arg.map_entities(increment_mapper, direct_mapping)).collect(),
+                }
+            }
             InstructionKind::RetVal { val } => {
                 InstructionKind::RetVal {
                     val: val.map_entities(increment_mapper, direct_mapping)
@@ -262,6 +153,27 @@ impl MapEntities for Instruction {
                     false_label: false_label.clone(),
                 }
             }
+            InstructionKind::Select { cond, val_true, val_false } => {
+                InstructionKind::Select {
+                    cond: cond.map_entities(increment_mapper, direct_mapping),
+                    val_true: val_true.map_entities(increment_mapper, direct_mapping),
+                    val_false: val_false.map_entities(increment_mapper, direct_mapping),
+                }
+            }
+            InstructionKind::Switch { val, default_label, cases } => {
+                InstructionKind::Switch {
+                    val: val.map_entities(increment_mapper, direct_mapping),
+                    default_label: default_label.clone(),
+                    cases: cases.clone(),
+                }
+            }
+            InstructionKind::Phi { args } => {
+                InstructionKind::Phi {
+                    args: args.iter()
+                        .map(|(ent, label)| (ent.map_entities(increment_mapper, direct_mapping), label.clone()))
+                        .collect(),
+                }
+            }
             InstructionKind::GetStructElementPtr { container_type_name, var, idx } => {
                 InstructionKind::GetStructElementPtr {
                     container_type_name: container_type_name.clone(),
@@ -279,13 +191,16 @@ impl MapEntities for Instruction {
             i => i.clone()
         };
         if let Some(ent) = self.get_meta() {
-            if let Entity::Register { n, t } = ent {
-                mapped_args_ent.with_result(Entity::Register {
-                    n: increment_mapper.map(*n),
-                    t: t.clone(),
-                })
-            } else {
-                panic!("Expected register entity, found: {:?}", ent)
+            match ent {
+                Entity::Register { n, t } => {
+                    mapped_args_ent.with_result(Entity::Register {
+                        n: increment_mapper.map(*n),
+                        t: t.clone(),
+                    })
+                }
+                // named registers (loop phi results) are not numbered
+                Entity::NamedRegister { .. } => mapped_args_ent.with_result(ent.clone()),
+                _ => panic!("Expected register entity, found: {:?}", ent),
             }
         } else {
             mapped_args_ent.without_result()
@@ -315,7 +230,7 @@ impl MapEntities for BasicBlock {
 
 #[cfg(test)]
 mod tests {
-    use crate::frontend::ast::{UnaryOperator, BinaryOperator};
+    use crate::frontend::ast::{UnaryOperator, BinaryOperator, Type};
 
     use super::*;
     use crate::backend::ir::InstructionKind::BinaryOp;
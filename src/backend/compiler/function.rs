@@ -1,15 +1,31 @@
-use crate::backend::context::{BlockContext, FunctionContext, GlobalContext};
+use std::collections::HashMap;
+
+use crate::backend::context::{BlockContext, FunctionContext, GlobalContext, LoopContext, Predecessor, TryContext};
 use crate::backend::builder::BlockBuilder;
-use crate::frontend::ast::{Function, ArgItem, Expression, ExpressionKind, Type, Reference, ReferenceKind, BinaryOperator, Statement, StatementKind, DeclItemKind, StatementOp, Arg};
+use crate::frontend::ast::{Function, ArgItem, Block, Expression, ExpressionKind, Type, Reference, ReferenceKind, BinaryOperator, Statement, StatementKind, DeclItemKind, Keyed, StatementOp, parse_format_string};
 use crate::meta::{TypeMeta, GetType};
 use crate::backend::ir::{FunctionDef, Entity, InstructionKind};
 
+/// location of the assignment target, that can be both loaded and stored
+enum ReferenceLocation {
+    /// local variable, stored in the block context
+    Variable { ident: String },
+    /// pointer to an object member or array item
+    Pointer { ptr: Entity, t: Type },
+}
+
 #[derive(Clone)]
 pub struct FunctionCompiler {
     builder: BlockBuilder,
     block_context: BlockContext,
     function_context: FunctionContext,
     global_context: GlobalContext,
+    loops: Vec<LoopContext>,
+    tries: Vec<TryContext>,
+    /// return type of the compiled function, necessary to propagate exceptions to the caller
+    ret_type: Type,
+    /// exceptions propagated out of main terminate the program
+    is_main: bool,
 }
 
 impl FunctionCompiler {
@@ -19,7 +35,11 @@ impl FunctionCompiler {
             builder: BlockBuilder::without_label(),
             block_context: BlockContext::new(),
             function_context: FunctionContext::new(),
-            global_context: global_context.clone()
+            global_context: global_context.clone(),
+            loops: Vec::new(),
+            tries: Vec::new(),
+            ret_type: Type::Void,
+            is_main: false,
         }
     }
 
@@ -41,6 +61,8 @@ impl FunctionCompiler {
         self.global_context = nested.global_context;
         self.function_context = nested.function_context;
         self.builder = nested.builder;
+        self.loops = nested.loops;
+        self.tries = nested.tries;
 
         self.block_context = nested.block_context;
         self.block_context.decrease_depth();
@@ -48,28 +70,408 @@ impl FunctionCompiler {
 
     /// shortcut function for creating a new basic block
     fn next_block(&mut self, label: String) {
-        let block = self.builder.build(
-            &mut self.function_context
-        );
+        let block = self.builder.build();
         self.function_context.push_block(block);
         self.builder = BlockBuilder::with_label(label);
     }
 
-    /// creates new basic block, but before that maps entities in the last block (loop body)
-    /// using mapping for the currently built block (loop condition)
-    fn complete_loop_block(&mut self, next_label: String) {
-        let cond_block = self.builder.build(
-            &mut self.function_context
-        );
-
-        let mapping = self.builder.get_entity_mapping(
-            &mut self.function_context
-        );
-        self.function_context.map_entities_in_last_block(mapping);
-        self.block_context.map_env(mapping);
-
-        self.function_context.push_block(cond_block);
-        self.builder = BlockBuilder::with_label(next_label);
+    /// creates new basic block that joins control flow from all given predecessors
+    /// (label of predecessor's last block, variable environment at its end),
+    /// variables that have different values in different predecessors are merged using phi instructions
+    fn join_blocks(&mut self, label: String, predecessors: Vec<Predecessor>) {
+        self.next_block(label);
+
+        // sorting identifiers guarantees the same order of phi instructions between compilations
+        let mut idents: Vec<String> = self.block_context.get_env_view().keys().cloned().collect();
+        idents.sort();
+        for ident in idents {
+            let phi_args: Vec<(Entity, String)> = predecessors.iter()
+                .filter_map(|(pred_label, pred_env)| {
+                    pred_env.get(&ident).map(|ent| (ent.clone(), pred_label.clone()))
+                })
+                .collect();
+            if phi_args.len() != predecessors.len() {
+                // variable was declared in only some of the predecessors, it is not available here
+                continue;
+            }
+
+            let first_ent = phi_args[0].0.clone();
+            if phi_args.iter().all(|(ent, _)| *ent == first_ent) {
+                self.block_context.update_variable(ident, first_ent);
+            } else {
+                // null constants don't carry the declared type of variable, so we skip them if possible
+                let phi_t = phi_args.iter()
+                    .map(|(ent, _)| ent.get_type())
+                    .find(|t| *t != Type::Null)
+                    .unwrap_or(Type::Null);
+                let phi_reg = self.function_context.new_register(phi_t);
+                let phi_instr = InstructionKind::Phi { args: phi_args };
+                self.builder.push_instruction(phi_instr.with_result(phi_reg.clone()));
+                self.block_context.update_variable(ident, phi_reg);
+            }
+        }
+    }
+
+    /// ends the current block with a jump to the new loop header, which begins with placeholder
+    /// phi instruction for every variable (filled in complete_loop, when all back-edges are known),
+    /// loop context is pushed to the stack to allow compilation of break and continue statements
+    fn begin_loop(&mut self, label: Option<String>, header_label: String, break_label: String, continue_label: String) {
+        let header_jump = InstructionKind::Jump { label: header_label.clone() };
+        self.builder.push_instruction(header_jump.without_result());
+        let in_label = self.builder.get_block_label();
+        let in_env = self.block_context.get_env_view();
+        self.next_block(header_label.clone());
+
+        // sorting identifiers guarantees the same order of phi instructions between compilations
+        let mut idents: Vec<String> = in_env.keys().cloned().collect();
+        idents.sort();
+        let mut header_phis = Vec::new();
+        for ident in idents {
+            let in_ent = in_env.get(&ident).unwrap().clone();
+            // named registers are used, so that removing unnecessary phis doesn't break register numbering
+            let phi_ent = Entity::NamedRegister {
+                name: format!("{}.{}", ident, header_label),
+                t: in_ent.get_type(),
+            };
+            let phi_instr = InstructionKind::Phi { args: vec![(in_ent.clone(), in_label.clone())] };
+            self.builder.push_instruction(phi_instr.with_result(phi_ent.clone()));
+            self.block_context.update_variable(ident.clone(), phi_ent.clone());
+            header_phis.push((ident, phi_ent, (in_ent, in_label.clone())));
+        }
+
+        self.loops.push(LoopContext {
+            label,
+            depth: self.block_context.get_depth(),
+            header_label,
+            header_phis,
+            break_label,
+            continue_label: Some(continue_label),
+            break_predecessors: Vec::new(),
+            continue_predecessors: Vec::new(),
+        });
+    }
+
+    /// compiles jump from the current block to the break (or continue) target of a loop with given label,
+    /// or the innermost loop if the label is not specified
+    fn compile_loop_jump(&mut self, label: &Option<String>, is_continue: bool) {
+        let loop_idx = self.loops.iter()
+            .rposition(|loop_ctx| loop_ctx.matches(label, is_continue))
+            .unwrap();
+
+        // try statements inside the loop are left, so their finally blocks are executed before the jump
+        if let Some(first_try) = self.tries.iter().position(|try_ctx| try_ctx.loop_count > loop_idx) {
+            self.compile_finally_blocks(first_try);
+            if self.builder.block_is_terminated() {
+                return;
+            }
+        }
+
+        let loop_ctx = &mut self.loops[loop_idx];
+
+        // variables declared inside the loop are not visible at jump destination
+        let pred_label = self.builder.get_block_label();
+        let pred_env = self.block_context.get_env_view_at_depth(loop_ctx.depth);
+        let jump_label = if is_continue {
+            loop_ctx.continue_predecessors.push((pred_label, pred_env));
+            loop_ctx.continue_label.clone().unwrap()
+        } else {
+            loop_ctx.break_predecessors.push((pred_label, pred_env));
+            loop_ctx.break_label.clone()
+        };
+        let jump_instr = InstructionKind::Jump { label: jump_label };
+        self.builder.push_instruction(jump_instr.without_result());
+    }
+
+    /// registers the current block (that exits the loop after evaluating condition) as a predecessor of loop end
+    fn add_loop_exit(&mut self) {
+        let pred_label = self.builder.get_block_label();
+        let pred_env = self.block_context.get_env_view();
+        self.loops.last_mut().unwrap().break_predecessors.push((pred_label, pred_env));
+    }
+
+    /// creates block that joins all continue statements of the innermost loop (and the end of its body),
+    /// returns false if there is no such statement and the block would be unreachable
+    fn begin_continue_block(&mut self, label: String) -> bool {
+        let continue_predecessors: Vec<_> = self.loops.last_mut().unwrap()
+            .continue_predecessors.drain(..)
+            .collect();
+        if continue_predecessors.is_empty() {
+            false
+        } else {
+            self.join_blocks(label, continue_predecessors);
+            true
+        }
+    }
+
+    /// completes the innermost loop: fills header phi instructions with values from back-edges
+    /// and creates the loop end block, joining all blocks that exit the loop,
+    /// then removes phi instructions that turned out to be unnecessary
+    fn complete_loop(&mut self, back_edges: Vec<Predecessor>) {
+        let loop_ctx = self.loops.pop().unwrap();
+        self.join_blocks(loop_ctx.break_label, loop_ctx.break_predecessors);
+
+        let mut phis: Vec<(Entity, Vec<(Entity, String)>)> = loop_ctx.header_phis.into_iter()
+            .map(|(ident, phi_ent, in_arg)| {
+                let mut args = vec![in_arg];
+                for (pred_label, pred_env) in back_edges.iter() {
+                    args.push((pred_env.get(&ident).unwrap().clone(), pred_label.clone()));
+                }
+                (phi_ent, args)
+            })
+            .collect();
+
+        // phi is unnecessary if it only merges a single value with itself,
+        // removing one phi may cause another one to become unnecessary, so we repeat until nothing changes
+        let mut mapping: HashMap<Entity, Entity> = HashMap::new();
+        loop {
+            let trivial_phi = phis.iter().enumerate()
+                .filter_map(|(idx, (phi_ent, args))| {
+                    let mut values: Vec<&Entity> = args.iter()
+                        .map(|(ent, _)| ent)
+                        .filter(|ent| *ent != phi_ent)
+                        .collect();
+                    values.dedup();
+                    if values.len() == 1 {
+                        Some((idx, values[0].clone()))
+                    } else {
+                        None
+                    }
+                })
+                .nth(0);
+            if let Some((idx, value)) = trivial_phi {
+                let (phi_ent, _) = phis.remove(idx);
+                let value = match value {
+                    // null constant doesn't carry the type of variable, so we take it from the phi
+                    Entity::Null { uuid, t: Type::Null } => Entity::Null { uuid, t: phi_ent.get_type() },
+                    value => value,
+                };
+                let mut phi_mapping = HashMap::new();
+                phi_mapping.insert(phi_ent.clone(), value.clone());
+                for (_, args) in phis.iter_mut() {
+                    for (ent, _) in args.iter_mut() {
+                        if let Some(mapped_ent) = phi_mapping.get(ent) {
+                            *ent = mapped_ent.clone();
+                        }
+                    }
+                }
+                for mapped_ent in mapping.values_mut() {
+                    if let Some(phi_value) = phi_mapping.get(mapped_ent) {
+                        *mapped_ent = phi_value.clone();
+                    }
+                }
+                mapping.insert(phi_ent, value);
+            } else {
+                break;
+            }
+        }
+
+        // replace placeholders with actual phi instructions and remove unnecessary phis from all instructions
+        let phi_instructions = phis.into_iter()
+            .map(|(phi_ent, args)| InstructionKind::Phi { args }.with_result(phi_ent))
+            .collect();
+        self.function_context.replace_phi_instructions(&loop_ctx.header_label, phi_instructions);
+        self.function_context.map_entities(&mapping);
+        self.builder.map_entities(&mapping);
+        self.block_context.map_env(&mapping);
+        for outer_loop_ctx in self.loops.iter_mut() {
+            for (_, env) in outer_loop_ctx.break_predecessors.iter_mut()
+                .chain(outer_loop_ctx.continue_predecessors.iter_mut()) {
+                for ent in env.values_mut() {
+                    if let Some(mapped_ent) = mapping.get(ent) {
+                        *ent = mapped_ent.clone();
+                    }
+                }
+            }
+        }
+        for try_ctx in self.tries.iter_mut() {
+            for (_, env) in try_ctx.handler_predecessors.iter_mut() {
+                for ent in env.values_mut() {
+                    if let Some(mapped_ent) = mapping.get(ent) {
+                        *ent = mapped_ent.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// pushes context of the try statement (or its catch clauses) to the stack,
+    /// exceptions thrown until it is removed jump to the given handler label
+    fn begin_try(&mut self, handler_label: String, finally: Option<Block<TypeMeta>>) {
+        self.tries.push(TryContext {
+            depth: self.block_context.get_depth(),
+            handler_label,
+            handler_predecessors: Vec::new(),
+            finally,
+            loop_count: self.loops.len(),
+        });
+    }
+
+    /// compiles jump from the current block to the handler of the innermost try statement,
+    /// if there is none, the exception is propagated to the caller by returning immediately
+    fn compile_exception_jump(&mut self) {
+        if let Some(try_ctx) = self.tries.last_mut() {
+            // variables declared inside the try statement are not visible in the handler
+            let pred_label = self.builder.get_block_label();
+            let pred_env = self.block_context.get_env_view_at_depth(try_ctx.depth);
+            try_ctx.handler_predecessors.push((pred_label, pred_env));
+            let jump_instr = InstructionKind::Jump { label: try_ctx.handler_label.clone() };
+            self.builder.push_instruction(jump_instr.without_result());
+        } else {
+            if self.is_main {
+                let uncaught_instr = InstructionKind::Call {
+                    func: String::from("__builtin_method__exception__uncaught__"),
+                    args: vec![],
+                };
+                self.builder.push_instruction(uncaught_instr.without_result());
+            }
+            // returned value is never used, as the caller checks for exception right after the call
+            let ret = match self.ret_type.clone() {
+                Type::Void => InstructionKind::RetVoid,
+                t => InstructionKind::RetVal { val: Self::get_default_value(t) },
+            };
+            self.builder.push_instruction(ret.without_result());
+        }
+    }
+
+    /// compiles check for exception thrown by the function called right before,
+    /// if there is one, it is propagated further
+    fn compile_exception_check(&mut self) {
+        let suffix = self.global_context.new_label_suffix();
+        let ok_label = format!("__call__ok__{}", suffix);
+        let exception_label = format!("__call__exception__{}", suffix);
+
+        let exception_ent = self.compile_pending_exception();
+        let cmp_instr = InstructionKind::BinaryOp {
+            op: BinaryOperator::Equal,
+            l: exception_ent,
+            r: Entity::Null { uuid: 0, t: Type::Str },
+        };
+        let cmp_ent = self.function_context.new_register(Type::Bool);
+        self.builder.push_instruction(cmp_instr.with_result(cmp_ent.clone()));
+        let cond_jump = InstructionKind::JumpCond {
+            cond: cmp_ent,
+            true_label: ok_label.clone(),
+            false_label: exception_label.clone(),
+        };
+        self.builder.push_instruction(cond_jump.without_result());
+
+        self.next_block(exception_label);
+        self.compile_exception_jump();
+        self.next_block(ok_label);
+    }
+
+    /// compiles call to the runtime function returning currently propagated exception (or null)
+    fn compile_pending_exception(&mut self) -> Entity {
+        let get_instr = InstructionKind::Call {
+            func: String::from("__builtin_method__exception__get__"),
+            args: vec![],
+        };
+        let exception_ent = self.function_context.new_register(Type::Str);
+        self.builder.push_instruction(get_instr.with_result(exception_ent.clone()));
+        exception_ent
+    }
+
+    /// compiles call to the runtime function that starts propagation of the exception
+    fn compile_throw(&mut self, exception_ent: Entity) {
+        let throw_instr = InstructionKind::Call {
+            func: String::from("__builtin_method__exception__throw__"),
+            args: vec![exception_ent],
+        };
+        self.builder.push_instruction(throw_instr.without_result());
+        self.compile_exception_jump();
+    }
+
+    /// compiles finally blocks of all try statements left by a jump (starting from the innermost one),
+    /// each block is compiled without its own try context, so that exceptions thrown there propagate further
+    fn compile_finally_blocks(&mut self, first_try: usize) {
+        let mut left_tries = Vec::new();
+        let mut left_levels = Vec::new();
+        while self.tries.len() > first_try && !self.builder.block_is_terminated() {
+            let try_ctx = self.tries.pop().unwrap();
+            if let Some(finally) = try_ctx.finally.clone() {
+                // variables declared inside the try statement are not visible in the finally block
+                left_levels.push(self.block_context.leave_to_depth(try_ctx.depth));
+                let meta = finally.get_meta().clone();
+                self.compile_statement(Statement::new(StatementKind::Block { block: finally }, meta));
+            }
+            left_tries.push(try_ctx);
+        }
+
+        // jump itself is compiled in the original context
+        for levels in left_levels.into_iter().rev() {
+            self.block_context.restore_levels(levels);
+        }
+        self.tries.extend(left_tries.into_iter().rev());
+    }
+
+    /// compiles check if the exception is an instance of given class, by comparing
+    /// the class id stored in its vtable with ids of the class and all its subclasses
+    fn compile_exception_match(&mut self, exception_ent: Entity, t: &Type) -> Entity {
+        let cls = if let Type::Class { ident } = t {
+            ident
+        } else {
+            panic!("Expected class type, got {}", t)
+        };
+        let struct_decl = self.global_context.get_struct_decl(cls);
+        let vtable_decl = self.global_context.get_vtable_decl(cls);
+        let vtable_t = Type::BuiltinClass {
+            ident: self.global_context.vtable_struct_name(cls)
+        };
+
+        // load vtable of the exception object
+        let cast_instr = InstructionKind::BitCast { ent: exception_ent, to: t.clone() };
+        let obj_ent = self.function_context.new_register(t.clone());
+        self.builder.push_instruction(cast_instr.with_result(obj_ent.clone()));
+        let vtable_gep_instr = InstructionKind::GetStructElementPtr {
+            container_type_name: struct_decl.llvm_name(),
+            var: obj_ent,
+            idx: Entity::Int { v: 0, uuid: 0 }
+        };
+        let vtable_gep_reg = self.function_context.new_register(vtable_t.reference());
+        self.builder.push_instruction(vtable_gep_instr.with_result(vtable_gep_reg.clone()));
+        let vtable_load_instr = InstructionKind::Load { ptr: vtable_gep_reg };
+        let vtable_ent = self.function_context.new_register(vtable_t);
+        self.builder.push_instruction(vtable_load_instr.with_result(vtable_ent.clone()));
+
+        // load class id, which is the first item of every vtable
+        // (vtables of classes without methods are empty and may share an address)
+        let id_gep_instr = InstructionKind::GetStructElementPtr {
+            container_type_name: vtable_decl.llvm_name(),
+            var: vtable_ent,
+            idx: Entity::Int { v: 0, uuid: 0 }
+        };
+        let id_gep_reg = self.function_context.new_register(Type::Int.reference());
+        self.builder.push_instruction(id_gep_instr.with_result(id_gep_reg.clone()));
+        let id_load_instr = InstructionKind::Load { ptr: id_gep_reg };
+        let id_ent = self.function_context.new_register(Type::Int);
+        self.builder.push_instruction(id_load_instr.with_result(id_ent.clone()));
+
+        let mut match_ent: Option<Entity> = None;
+        for subclass in self.global_context.get_subclasses(cls) {
+            let subclass_id = self.global_context.get_vtable_decl(&subclass).class_id;
+            let cmp_instr = InstructionKind::BinaryOp {
+                op: BinaryOperator::Equal,
+                l: id_ent.clone(),
+                r: Entity::Int { v: subclass_id, uuid: self.function_context.new_uuid() },
+            };
+            let cmp_ent = self.function_context.new_register(Type::Bool);
+            self.builder.push_instruction(cmp_instr.with_result(cmp_ent.clone()));
+
+            match_ent = match match_ent {
+                None => Some(cmp_ent),
+                Some(prev_ent) => {
+                    let or_instr = InstructionKind::BinaryOp {
+                        op: BinaryOperator::Or,
+                        l: prev_ent,
+                        r: cmp_ent,
+                    };
+                    let or_ent = self.function_context.new_register(Type::Bool);
+                    self.builder.push_instruction(or_instr.with_result(or_ent.clone()));
+                    Some(or_ent)
+                }
+            };
+        }
+        match_ent.unwrap()
     }
 
     /// clone entity, ensuring its uniqueness (new uuid) if it is a constant
@@ -83,17 +485,216 @@ impl FunctionCompiler {
                 v,
                 uuid: self.function_context.new_uuid(),
             },
+            Entity::Long { v, uuid: _ } => Entity::Long {
+                v,
+                uuid: self.function_context.new_uuid(),
+            },
             Entity::Bool { v, uuid } => Entity::Bool {
                 v,
                 uuid: self.function_context.new_uuid(),
             },
+            Entity::Char { v, uuid: _ } => Entity::Char {
+                v,
+                uuid: self.function_context.new_uuid(),
+            },
+            Entity::Double { v, uuid: _ } => Entity::Double {
+                v,
+                uuid: self.function_context.new_uuid(),
+            },
             reg_entity => reg_entity,
         }
     }
 
-    // TODO: This is not as modular as it looks, probably beter to have separate functions for load & store
+    /// get entity with the current value of a variable, global variables are loaded from memory
+    /// (local variables shadow global ones)
+    fn get_variable(&mut self, ident: &String) -> Entity {
+        if self.block_context.has_variable(ident) {
+            return self.block_context.get_variable(ident);
+        }
+        let decl = self.global_context.get_global_variable(ident);
+        let load_reg = self.function_context.new_register(decl.t.clone());
+        let load_instr = InstructionKind::Load { ptr: decl.get_ptr() };
+        self.builder.push_instruction(load_instr.with_result(load_reg.clone()));
+        load_reg
+    }
+
+    /// get entity with the current value of a variable or a member of self (if the class of self is given),
+    /// members are loaded from the object like in TypedObject references
+    fn get_variable_or_member(&mut self, ident: &String, self_cls: &Option<String>) -> Entity {
+        match self_cls {
+            Some(self_cls) => {
+                let self_ent = self.get_variable(&String::from("self"));
+                let struct_decl = self.global_context.get_struct_decl(self_cls);
+                let field_idx = *struct_decl.field_env.get(ident).unwrap();
+                let field_t = struct_decl.fields[field_idx as usize].clone();
+                let ptr = self.compile_object_gep(self_cls, ident, self_ent);
+
+                let load_reg = self.function_context.new_register(field_t);
+                let load_instr = InstructionKind::Load { ptr };
+                self.builder.push_instruction(load_instr.with_result(load_reg.clone()));
+                load_reg
+            }
+            None => self.get_variable(ident),
+        }
+    }
+
+    /// set new value of a variable, local variables are updated in the environment,
+    /// while global variables are stored in memory
+    fn set_variable(&mut self, ident: &String, val: Entity) {
+        if self.block_context.has_variable(ident) {
+            // update local variable environment to reflect the change
+            let entity = self.make_unique_entity(val);
+            self.block_context.update_variable(ident.clone(), entity);
+        } else {
+            let decl = self.global_context.get_global_variable(ident);
+            let store_instr = InstructionKind::Store { val, ptr: decl.get_ptr() };
+            self.builder.push_instruction(store_instr.without_result());
+        }
+    }
+
+    /// compiles instructions necessary to access member of the object as a reference entity
     fn compile_object_gep(&mut self, class_name: &String, member_name: &String, obj_ent: Entity) -> Entity {
-        unimplemented!()
+        let struct_decl = self.global_context.get_struct_decl(class_name);
+        let field_idx = *struct_decl.field_env.get(member_name).unwrap();
+        let field_t = struct_decl.fields[field_idx as usize].clone();
+
+        let gep_instr = InstructionKind::GetStructElementPtr {
+            container_type_name: struct_decl.llvm_name(),
+            var: obj_ent,
+            idx: Entity::Int { v: field_idx, uuid: 0 }
+        };
+        let gep_reg = self.function_context.new_register(field_t.reference());
+        self.builder.push_instruction(gep_instr.with_result(gep_reg.clone()));
+        gep_reg
+    }
+
+    /// compiles the location of the assignment target, so that it can be loaded and stored
+    /// without evaluating the reference (eg. array index) more than once
+    fn compile_reference_location(&mut self, r: &Reference<TypeMeta>) -> ReferenceLocation {
+        match &r.item {
+            ReferenceKind::Ident { ident } => {
+                ReferenceLocation::Variable { ident: ident.clone() }
+            }
+            ReferenceKind::TypedObject { obj, cls, field } => {
+                let obj_ent = self.get_variable(obj);
+                let ptr = self.compile_object_gep(cls, field, obj_ent);
+                ReferenceLocation::Pointer { ptr, t: r.get_type() }
+            }
+            ReferenceKind::TypedMemberObject { self_cls, obj, obj_class, field } => {
+                // load the object from a member of self first
+                let self_ent = self.get_variable(&String::from("self"));
+                let member_ptr = self.compile_object_gep(self_cls, obj, self_ent);
+                let obj_ent = self.function_context.new_register(Type::Class { ident: obj_class.clone() });
+                let load_instr = InstructionKind::Load { ptr: member_ptr };
+                self.builder.push_instruction(load_instr.with_result(obj_ent.clone()));
+
+                let ptr = self.compile_object_gep(obj_class, field, obj_ent);
+                ReferenceLocation::Pointer { ptr, t: r.get_type() }
+            }
+            ReferenceKind::Array { arr, idx } => {
+                let array_ent = self.get_variable(arr);
+                let idx_ent = self.compile_expression(idx.as_ref().clone());
+                let ptr = self.compile_array_gep(array_ent.get_array_item_t(), array_ent, idx_ent);
+                ReferenceLocation::Pointer { ptr, t: r.get_type() }
+            }
+            r => unreachable!("cannot assign to reference {:?}", r)
+        }
+    }
+
+    /// compiles instructions loading value from the previously compiled reference location
+    fn load_reference_location(&mut self, location: &ReferenceLocation) -> Entity {
+        match location {
+            ReferenceLocation::Variable { ident } => {
+                self.get_variable(ident)
+            }
+            ReferenceLocation::Pointer { ptr, t } => {
+                let load_reg = self.function_context.new_register(t.clone());
+                let load_instr = InstructionKind::Load { ptr: ptr.clone() };
+                self.builder.push_instruction(load_instr.with_result(load_reg.clone()));
+                load_reg
+            }
+        }
+    }
+
+    /// compiles instructions storing value in the previously compiled reference location
+    fn store_reference_location(&mut self, location: &ReferenceLocation, val: Entity) {
+        match location {
+            ReferenceLocation::Variable { ident } => {
+                self.set_variable(ident, val);
+            }
+            ReferenceLocation::Pointer { ptr, t: _ } => {
+                let store_instr = InstructionKind::Store { val, ptr: ptr.clone() };
+                self.builder.push_instruction(store_instr.without_result());
+            }
+        }
+    }
+
+    /// get binary operator that performs increment or decrement
+    fn get_mut_operator(op: &StatementOp) -> BinaryOperator {
+        match op {
+            StatementOp::Increment => BinaryOperator::Plus,
+            StatementOp::Decrement => BinaryOperator::Minus,
+        }
+    }
+
+    /// get integer constant of the given integral type
+    fn get_integral_constant(t: &Type, v: i32) -> Entity {
+        match t {
+            Type::Long => Entity::Long { v: v as i64, uuid: 0 },
+            _ => Entity::Int { v, uuid: 0 },
+        }
+    }
+
+    /// compiles binary operation on already evaluated arguments, strings are concatenated
+    fn compile_binary_operation(&mut self, op: BinaryOperator, left_ent: Entity, right_ent: Entity, result_t: Type) -> Entity {
+        // for strings, use concatenation function instead of llvm operator
+        let instr = if left_ent.get_type() == Type::Str && op == BinaryOperator::Plus {
+            InstructionKind::Call {
+                func: String::from("__builtin_method__str__concat__"),
+                args: vec![left_ent, right_ent],
+            }
+        } else if left_ent.get_type() == Type::Str && result_t == Type::Bool {
+            // strings are compared by value, result of the runtime function is compared with 0
+            let cmp_instr = InstructionKind::Call {
+                func: String::from("__builtin_method__str__compare__"),
+                args: vec![left_ent, right_ent],
+            };
+            let cmp_ent = self.function_context.new_register(Type::Int);
+            self.builder.push_instruction(cmp_instr.with_result(cmp_ent.clone()));
+            InstructionKind::BinaryOp {
+                op,
+                l: cmp_ent,
+                r: Entity::Int { v: 0, uuid: 0 },
+            }
+        } else if op == BinaryOperator::ShiftLeft
+            || op == BinaryOperator::ShiftRight
+            || op == BinaryOperator::ShiftRightLogical {
+            // only 5 (or 6 for long) lowest bits of the shift distance are used (like in Java),
+            // shifting by the bit width or more would produce an undefined value in llvm
+            let shift_t = right_ent.get_type();
+            let bit_width = if shift_t == Type::Long { 64 } else { 32 };
+            let mask_instr = InstructionKind::BinaryOp {
+                op: BinaryOperator::BitAnd,
+                l: right_ent,
+                r: Self::get_integral_constant(&shift_t, bit_width - 1),
+            };
+            let mask_ent = self.function_context.new_register(shift_t);
+            self.builder.push_instruction(mask_instr.with_result(mask_ent.clone()));
+            InstructionKind::BinaryOp {
+                op,
+                l: left_ent,
+                r: mask_ent,
+            }
+        } else {
+            InstructionKind::BinaryOp {
+                op,
+                l: left_ent,
+                r: right_ent,
+            }
+        };
+        let result_ent = self.function_context.new_register(result_t);
+        self.builder.push_instruction(instr.with_result(result_ent.clone()));
+        result_ent
     }
 
     /// compiles instructions necessary to access array_ent at idx_ent as a reference entity
@@ -126,15 +727,374 @@ impl FunctionCompiler {
         item_ptr_ent
     }
 
-    /// calculate size of a single value from reference
+    /// allocates array struct of type t with length_ent items, returns pointers to the struct
+    /// and to its raw array (so that the caller can fill it)
+    fn compile_array_allocation(&mut self, t: Type, length_ent: Entity) -> (Entity, Entity) {
+        let void_ptr_t = Type::Str; // apparently void* in C is i8* in LLVM
+        let arr_item_t = Self::get_array_item_type(&t);
+
+        // register new array type that has to be declared for our program
+        let arr_struct_decl = self.global_context.get_or_declare_array_struct(&arr_item_t);
+
+        // calculate size of the array (in bytes)
+        let item_size_ent = Self::get_size(&arr_item_t);
+        let byte_count_instr = InstructionKind::BinaryOp {
+            op: BinaryOperator::Times,
+            l: length_ent.clone(),
+            r: item_size_ent
+        };
+        let bytes_size_ent = self.function_context.new_register(Type::Int);
+        self.builder.push_instruction(byte_count_instr.with_result(bytes_size_ent.clone()));
+
+        // allocate memory for the array
+        let raw_array_malloc = InstructionKind::Call {
+            func: String::from("__builtin_method__array__init__"),
+            args: vec![bytes_size_ent]
+        };
+        let malloc_result_ent = self.function_context.new_register(void_ptr_t.clone());
+        self.builder.push_instruction(raw_array_malloc.with_result(malloc_result_ent.clone()));
+
+        // cast raw array to its appropriate type
+        let raw_array_t = Type::Reference { t: Box::new(arr_item_t.clone()) };
+        let raw_array_cast = InstructionKind::BitCast {
+            ent: malloc_result_ent,
+            to: raw_array_t.clone()
+        };
+        let raw_array_ent = self.function_context.new_register(raw_array_t.clone());
+        self.builder.push_instruction(raw_array_cast.with_result(raw_array_ent.clone()));
+
+        // load size of array struct
+        let load_struct_size = InstructionKind::Load {
+            ptr: Entity::GlobalConst { name: self.global_context.get_array_struct_size_name(&arr_item_t), t: Type::Int.reference() }
+        };
+        let struct_size_ent = self.function_context.new_register(Type::Int);
+        self.builder.push_instruction(load_struct_size.with_result(struct_size_ent.clone()));
+
+        // use array init to allocate array struct as well
+        let struct_init = InstructionKind::Call {
+            func: String::from("__builtin_method__array__init__"),
+            args: vec![struct_size_ent]
+        };
+        let array_init_ent = self.function_context.new_register(void_ptr_t);
+        self.builder.push_instruction(struct_init.with_result(array_init_ent.clone()));
+
+        // cast the result to appropriate type
+        let array_struct_cast = InstructionKind::BitCast {
+            ent: array_init_ent,
+            to: t.clone()
+        };
+        let array_struct_ptr_ent = self.function_context.new_register(t.clone());
+        self.builder.push_instruction(array_struct_cast.with_result(array_struct_ptr_ent.clone()));
+
+        // set length
+        let length_gep = InstructionKind::GetStructElementPtr {
+            container_type_name: arr_struct_decl.llvm_name(),
+            var: array_struct_ptr_ent.clone(),
+            idx: Entity::Int { v: 0, uuid: 0 }
+        };
+        let length_ptr = self.function_context.new_register(Type::Int.reference());
+        self.builder.push_instruction(length_gep.with_result(length_ptr.clone()));
+
+        let store_length = InstructionKind::Store {
+            val: length_ent,
+            ptr: length_ptr
+        };
+        self.builder.push_instruction(store_length.without_result());
+
+        // move the allocated raw array into array struct
+        let raw_array_gep = InstructionKind::GetStructElementPtr {
+            container_type_name: arr_struct_decl.llvm_name(),
+            var: array_struct_ptr_ent.clone(),
+            idx: Entity::Int { v: 1, uuid: 0 }
+        };
+        let raw_array_ptr = self.function_context.new_register(raw_array_t.reference());
+        self.builder.push_instruction(raw_array_gep.with_result(raw_array_ptr.clone()));
+
+        let store_array = InstructionKind::Store {
+            val: raw_array_ent.clone(),
+            ptr: raw_array_ptr
+        };
+        self.builder.push_instruction(store_array.without_result());
+
+        (array_struct_ptr_ent, raw_array_ent)
+    }
+
+    /// allocates a new, empty list of the given type
+    fn compile_list_init(&mut self, t: Type) -> Entity {
+        let item_size_ent = match &t {
+            Type::List { item_t } => Self::get_size(item_t),
+            t => panic!("expected list type, got {}", t),
+        };
+        let instr = InstructionKind::Call {
+            func: String::from("__builtin_method__list__init__"),
+            args: vec![item_size_ent]
+        };
+        let result_ent = self.function_context.new_register(t);
+        self.builder.push_instruction(instr.with_result(result_ent.clone()));
+        result_ent
+    }
+
+    /// calls runtime function that returns a pointer to the slot of list item (as void*),
+    /// casts the result to a pointer to the item type
+    fn compile_list_slot(&mut self, func: &str, list_ent: Entity, args: Vec<Entity>) -> Entity {
+        let item_t = list_ent.get_array_item_t();
+        let mut call_args = vec![list_ent];
+        call_args.extend(args);
+        let call_instr = InstructionKind::Call {
+            func: String::from(func),
+            args: call_args
+        };
+        let slot_ent = self.function_context.new_register(Type::Str);
+        self.builder.push_instruction(call_instr.with_result(slot_ent.clone()));
+
+        self.cast_to_expected_type(slot_ent, item_t.reference())
+    }
+
+    /// compiles call of a builtin list method, the list is stored in variable ident
+    fn compile_list_method(
+        &mut self, ident: &String, self_cls: &Option<String>, method: &str, args: Vec<Expression<TypeMeta>>,
+    ) -> Entity {
+        let list_ent = self.get_variable_or_member(ident, self_cls);
+        let item_t = list_ent.get_array_item_t();
+        let mut arg_entities: Vec<Entity> = args.into_iter()
+            .map(|a| self.compile_expression(a))
+            .collect();
+
+        match method {
+            "size" => {
+                let instr = InstructionKind::Call {
+                    func: String::from("__builtin_method__list__size__"),
+                    args: vec![list_ent]
+                };
+                let result_ent = self.function_context.new_register(Type::Int);
+                self.builder.push_instruction(instr.with_result(result_ent.clone()));
+                result_ent
+            }
+            "push" | "set" | "insert" => {
+                // value is always the last argument, runtime only needs the index
+                let val_ent = arg_entities.pop().unwrap();
+                let val_ent = self.cast_to_expected_type(val_ent, item_t);
+                let func = match method {
+                    "push" => "__builtin_method__list__push__",
+                    "set" => "__builtin_method__list__at__",
+                    _ => "__builtin_method__list__insert__",
+                };
+                let slot_ent = self.compile_list_slot(func, list_ent, arg_entities);
+                let store_instr = InstructionKind::Store {
+                    val: val_ent,
+                    ptr: slot_ent
+                };
+                self.builder.push_instruction(store_instr.without_result());
+                // typechecker guarantees we don't use this so just return a placeholder
+                Entity::Null { uuid: 0, t: Type::Null }
+            }
+            "pop" | "get" | "remove" => {
+                // removed items remain in the list buffer until next modification
+                let func = match method {
+                    "pop" => "__builtin_method__list__pop__",
+                    "get" => "__builtin_method__list__at__",
+                    _ => "__builtin_method__list__remove__",
+                };
+                let slot_ent = self.compile_list_slot(func, list_ent, arg_entities);
+                let load_instr = InstructionKind::Load { ptr: slot_ent };
+                let result_ent = self.function_context.new_register(item_t);
+                self.builder.push_instruction(load_instr.with_result(result_ent.clone()));
+                result_ent
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// allocates a new, empty map of the given type
+    fn compile_map_init(&mut self, t: Type) -> Entity {
+        let (string_keys, val_size_ent) = match &t {
+            Type::Map { key_t, val_t } => (**key_t == Type::Str, Self::get_size(val_t)),
+            t => panic!("expected map type, got {}", t),
+        };
+        let instr = InstructionKind::Call {
+            func: String::from("__builtin_method__map__init__"),
+            args: vec![Entity::Bool { v: string_keys, uuid: 0 }, val_size_ent]
+        };
+        let result_ent = self.function_context.new_register(t);
+        self.builder.push_instruction(instr.with_result(result_ent.clone()));
+        result_ent
+    }
+
+    /// calls runtime function implementing map method, functions that take a key
+    /// have separate versions for int and string keys
+    fn compile_map_call(&mut self, method: &str, map_ent: Entity, args: Vec<Entity>) -> Entity {
+        let (key_t, val_t) = match map_ent.get_type() {
+            Type::Map { key_t, val_t } => (*key_t, *val_t),
+            t => panic!("expected map type, got {}", t),
+        };
+        let (func, result_t) = match method {
+            "size" => (String::from("__builtin_method__map__size__"), Type::Int),
+            "keys" => (String::from("__builtin_method__map__keys__"), Type::List { item_t: Box::new(key_t) }),
+            method => {
+                let key_kind = if key_t == Type::Str { "str" } else { "int" };
+                let result_t = match method {
+                    "containsKey" => Type::Bool,
+                    "remove" => Type::Void,
+                    // put and get return pointer to the value slot
+                    _ => Type::Str,
+                };
+                (format!("__builtin_method__map_{}__{}__", key_kind, method), result_t)
+            }
+        };
+        let mut call_args = vec![map_ent];
+        call_args.extend(args);
+        let instr = InstructionKind::Call { func, args: call_args };
+        match result_t {
+            Type::Void => {
+                self.builder.push_instruction(instr.without_result());
+                // typechecker guarantees we don't use this so just return a placeholder
+                Entity::Null { uuid: 0, t: Type::Null }
+            }
+            Type::Str => {
+                let slot_ent = self.function_context.new_register(Type::Str);
+                self.builder.push_instruction(instr.with_result(slot_ent.clone()));
+                self.cast_to_expected_type(slot_ent, val_t.reference())
+            }
+            t => {
+                let result_ent = self.function_context.new_register(t);
+                self.builder.push_instruction(instr.with_result(result_ent.clone()));
+                result_ent
+            }
+        }
+    }
+
+    /// compiles call of a builtin map method, the map is stored in variable ident
+    fn compile_map_method(
+        &mut self, ident: &String, self_cls: &Option<String>, method: &str, args: Vec<Expression<TypeMeta>>,
+    ) -> Entity {
+        let map_ent = self.get_variable_or_member(ident, self_cls);
+        let mut arg_entities: Vec<Entity> = args.into_iter()
+            .map(|a| self.compile_expression(a))
+            .collect();
+
+        match method {
+            "put" => {
+                let val_t = match map_ent.get_type() {
+                    Type::Map { val_t, .. } => *val_t,
+                    t => panic!("expected map type, got {}", t),
+                };
+                let val_ent = arg_entities.pop().unwrap();
+                let val_ent = self.cast_to_expected_type(val_ent, val_t);
+                let slot_ent = self.compile_map_call(method, map_ent, arg_entities);
+                let store_instr = InstructionKind::Store {
+                    val: val_ent,
+                    ptr: slot_ent
+                };
+                self.builder.push_instruction(store_instr.without_result());
+                Entity::Null { uuid: 0, t: Type::Null }
+            }
+            "get" => {
+                let slot_ent = self.compile_map_call(method, map_ent, arg_entities);
+                let val_t = match slot_ent.get_type() {
+                    Type::Reference { t } => *t,
+                    t => panic!("expected reference type, got {}", t),
+                };
+                let load_instr = InstructionKind::Load { ptr: slot_ent };
+                let result_ent = self.function_context.new_register(val_t);
+                self.builder.push_instruction(load_instr.with_result(result_ent.clone()));
+                result_ent
+            }
+            method => self.compile_map_call(method, map_ent, arg_entities),
+        }
+    }
+
+    /// get zero value of the given type, returned by functions that propagate an exception
+    fn get_default_value(t: Type) -> Entity {
+        match t {
+            Type::Int => Entity::Int { v: 0, uuid: 0 },
+            Type::Long => Entity::Long { v: 0, uuid: 0 },
+            Type::Bool => Entity::Bool { v: false, uuid: 0 },
+            Type::Char => Entity::Char { v: 0, uuid: 0 },
+            Type::Double => Entity::Double { v: 0f64.to_bits(), uuid: 0 },
+            t => Entity::Null { uuid: 0, t },
+        }
+    }
+
+    /// compiles the value of a variable declared without initialization
+    fn compile_default_init(&mut self, t: &Type) -> Entity {
+        let result_t = t.clone();
+        match t {
+            Type::Int => Entity::Int {
+                v: 0,
+                uuid: self.function_context.new_uuid(),
+            },
+            Type::Bool => Entity::Bool {
+                v: false,
+                uuid: self.function_context.new_uuid(),
+            },
+            Type::Char => Entity::Char {
+                v: 0,
+                uuid: self.function_context.new_uuid(),
+            },
+            Type::Long => Entity::Long {
+                v: 0,
+                uuid: self.function_context.new_uuid(),
+            },
+            Type::Double => Entity::Double {
+                v: 0f64.to_bits(),
+                uuid: self.function_context.new_uuid(),
+            },
+            Type::Str => {
+                let default_init = InstructionKind::Call {
+                    func: String::from("__builtin_method__str__init__"),
+                    args: vec![Entity::Int { v: 0, uuid: 0 }],
+                };
+                let call_ret_ent = self.function_context.new_register(Type::Str);
+                self.builder.push_instruction(
+                    default_init.with_result(call_ret_ent.clone())
+                );
+                call_ret_ent
+            }
+            Type::Class { ident } => self.compile_object_init(ident, result_t),
+            Type::Array { item_t } => {
+                Entity::Null {
+                    uuid: self.function_context.new_uuid(),
+                    t: Type::Array { item_t: item_t.clone() }
+                }
+            }
+            Type::List { .. } => self.compile_list_init(t.clone()),
+            Type::Map { .. } => self.compile_map_init(t.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// allocate a new object of the class and set the fields which have initial values
+    fn compile_object_init(&mut self, class_name: &String, result_t: Type) -> Entity {
+        let call_instr = InstructionKind::Call {
+            func: self.global_context.get_init_name(class_name),
+            args: vec![],
+        };
+        let obj_ent = self.function_context.new_register(result_t);
+        self.builder.push_instruction(call_instr.with_result(obj_ent.clone()));
+
+        for (field, val) in self.global_context.get_field_initializers(class_name) {
+            let val_ent = self.compile_expression(val);
+            let ptr = self.compile_object_gep(class_name, &field, obj_ent.clone());
+            let store_instr = InstructionKind::Store { val: val_ent, ptr };
+            self.builder.push_instruction(store_instr.without_result());
+        }
+        obj_ent
+    }
+
+    /// calculate size of a single value of the given type (in bytes)
     fn get_size(t: &Type) -> Entity {
-        const PTR_SIZE: i32 = 4;
+        const PTR_SIZE: i32 = 8;
         match t {
             Type::Int => Entity::Int { v: 4, uuid: 0 },
+            Type::Long => Entity::Int { v: 8, uuid: 0 },
             Type::Str => Entity::Int { v: PTR_SIZE, uuid: 0 },
             Type::Bool => Entity::Int { v: 1, uuid: 0 },
+            Type::Char => Entity::Int { v: 1, uuid: 0 },
+            Type::Double => Entity::Int { v: 8, uuid: 0 },
             Type::Class { .. } => Entity::Int { v: PTR_SIZE, uuid: 0 },
             Type::Array { .. } => Entity::Int { v: PTR_SIZE, uuid: 0 },
+            Type::List { .. } => Entity::Int { v: PTR_SIZE, uuid: 0 },
+            Type::Map { .. } => Entity::Int { v: PTR_SIZE, uuid: 0 },
             Type::Reference { .. } => Entity::Int { v: PTR_SIZE, uuid: 0 },
             _ => Entity::Int { v: 0, uuid: 0 },
         }
@@ -158,6 +1118,213 @@ impl FunctionCompiler {
         }
     }
 
+    /// interpolated string is built with a single call to the runtime formatting function,
+    /// the format string is created at compile time based on types of the interpolated expressions
+    fn compile_interpolation(&mut self, parts: Vec<String>, exprs: Vec<Expression<TypeMeta>>) -> Entity {
+        let mut format = parts[0].replace('%', "%%");
+        let mut arg_entities = Vec::new();
+        for (expr, part) in exprs.into_iter().zip(parts.iter().skip(1)) {
+            let (conversion, arg_ent) = self.compile_format_arg(expr);
+            format.push('%');
+            format.push_str(conversion);
+            format.push_str(&part.replace('%', "%%"));
+            arg_entities.push(arg_ent);
+        }
+        self.compile_format_call("__builtin_method__str__format__", &format, arg_entities, Type::Str)
+    }
+
+    /// print, println and printf builtins are lowered to a single call to the runtime printing
+    /// function, with the format string created at compile time (printf format is already checked)
+    fn compile_print(&mut self, ident: &String, args: Vec<Expression<TypeMeta>>) -> Entity {
+        let mut arg_entities = Vec::new();
+        let format = if ident == "printf" {
+            let mut args = args.into_iter();
+            let (parts, specs) = match args.next().map(|format| format.item) {
+                Some(ExpressionKind::LitStr { val }) => parse_format_string(&val).unwrap(),
+                _ => unreachable!("format of printf is checked by the typechecker"),
+            };
+            let mut format = parts[0].clone();
+            for ((spec, part), expr) in specs.iter().zip(parts.iter().skip(1)).zip(args) {
+                // long values require length modifier in the C format
+                let length = if expr.get_type() == Type::Long { "ll" } else { "" };
+                let (_, arg_ent) = self.compile_format_arg(expr);
+                format.push_str(&format!("%{}{}{}", spec.modifiers, length, spec.conversion));
+                format.push_str(part);
+                arg_entities.push(arg_ent);
+            }
+            format
+        } else {
+            // print and println separate the values with spaces
+            let mut conversions = Vec::new();
+            for expr in args {
+                let (conversion, arg_ent) = self.compile_format_arg(expr);
+                conversions.push(format!("%{}", conversion));
+                arg_entities.push(arg_ent);
+            }
+            let mut format = conversions.join(" ");
+            if ident == "println" {
+                format.push_str("\\n");
+            }
+            format
+        };
+        self.compile_format_call("__builtin_method__print__", &format, arg_entities, Type::Void)
+    }
+
+    /// convert a value to the argument of runtime formatting functions, returns it together with
+    /// the C conversion (including length modifier) used to format the value by default
+    fn compile_format_arg(&mut self, expr: Expression<TypeMeta>) -> (&'static str, Entity) {
+        let t = expr.get_type();
+        let expr_ent = self.compile_expression(expr);
+        match &t {
+            Type::Int => ("d", expr_ent),
+            Type::Long => ("lld", expr_ent),
+            // same format as in printDouble
+            Type::Double => (".15g", expr_ent),
+            Type::Char => {
+                // variadic arguments are promoted to int, so the char is converted explicitly
+                let instr = InstructionKind::Call {
+                    func: self.global_context.get_native_function_name(&String::from("charToInt")),
+                    args: vec![expr_ent],
+                };
+                let int_ent = self.function_context.new_register(Type::Int);
+                self.builder.push_instruction(instr.with_result(int_ent.clone()));
+                ("c", int_ent)
+            }
+            Type::Bool => {
+                let true_ent = self.compile_string_constant("true");
+                let false_ent = self.compile_string_constant("false");
+                let instr = InstructionKind::Select {
+                    cond: expr_ent,
+                    val_true: true_ent,
+                    val_false: false_ent,
+                };
+                let str_ent = self.function_context.new_register(Type::Str);
+                self.builder.push_instruction(instr.with_result(str_ent.clone()));
+                ("s", str_ent)
+            }
+            Type::Str => ("s", expr_ent),
+            Type::Class { ident } => {
+                let str_ent = self.compile_method_call(expr_ent, ident, &String::from("toString"), vec![]);
+                ("s", str_ent)
+            }
+            t => unreachable!("typechecker does not allow formatting values of type {}", t),
+        }
+    }
+
+    /// call variadic runtime function taking the format string (without quotes) and its arguments
+    fn compile_format_call(&mut self, func: &str, format: &str, mut arg_entities: Vec<Entity>, ret_t: Type) -> Entity {
+        let mut args = vec![self.compile_string_constant(format)];
+        args.append(&mut arg_entities);
+        let instr = InstructionKind::CallVariadic {
+            func: String::from(func),
+            fixed_args: 1,
+            args,
+        };
+        if ret_t == Type::Void {
+            self.builder.push_instruction(instr.without_result());
+            Entity::Null { uuid: 0, t: Type::Null }
+        } else {
+            let result_ent = self.function_context.new_register(ret_t);
+            self.builder.push_instruction(instr.with_result(result_ent.clone()));
+            result_ent
+        }
+    }
+
+    /// load a string literal (given without quotes) as i8* entity
+    fn compile_string_constant(&mut self, val: &str) -> Entity {
+        let expr_kind = ExpressionKind::LitStr { val: format!("\"{}\"", val) };
+        self.compile_expression(Expression::new(expr_kind, TypeMeta { t: Type::Str }))
+    }
+
+    /// call a method on the object of class cls, its implementation is taken from the object's vtable
+    fn compile_method_call(
+        &mut self, obj_ent: Entity, cls: &String, method: &String, args: Vec<Expression<TypeMeta>>,
+    ) -> Entity {
+        let struct_decl = self.global_context.get_struct_decl(cls);
+
+        let vtable_t = Type::BuiltinClass {
+            ident: self.global_context.vtable_struct_name(cls)
+        };
+
+        // get vtable reference
+        let vtable_gep_instr = InstructionKind::GetStructElementPtr {
+            container_type_name: struct_decl.llvm_name(),
+            var: obj_ent.clone(),
+            idx: Entity::Int { v: 0, uuid: 0 }
+        };
+        let vtable_gep_reg = self.function_context.new_register(vtable_t.reference());
+        self.builder.push_instruction(vtable_gep_instr.with_result(vtable_gep_reg.clone()));
+
+        // load vtable
+        let vtable_load_instr = InstructionKind::Load {
+            ptr: vtable_gep_reg
+        };
+        let vtable_ent = self.function_context.new_register(vtable_t.clone());
+        self.builder.push_instruction(vtable_load_instr.with_result(vtable_ent.clone()));
+
+        // get reference to method from vtable
+        let vtable_struct = self.global_context.get_vtable_decl(cls);
+        let method_idx = vtable_struct.method_env.get(method).unwrap();
+        let method_gep_instr = InstructionKind::GetStructElementPtr {
+            container_type_name: vtable_struct.llvm_name(),
+            var: vtable_ent,
+            idx: Entity::Int { v: vtable_struct.method_item_idx(method), uuid: 0 }
+        };
+        let method_t = vtable_struct.methods[*method_idx as usize].0.clone();
+        let method_ptr = self.function_context.new_register(method_t.reference().reference());
+        self.builder.push_instruction(method_gep_instr.with_result(method_ptr.clone()));
+
+        // load method
+        let method_load_instr = InstructionKind::Load {
+            ptr: method_ptr
+        };
+        let method_ent = self.function_context.new_register(method_t.reference());
+        self.builder.push_instruction(method_load_instr.with_result(method_ent.clone()));
+
+        // build args: self, compiled results of passed expressions
+        let mut args_with_self = Vec::new();
+
+        // TODO: Refactor, this was also repeated in ClassCompiler
+        let method_arg_types = if let Type::Function { args, ret: _ } = method_t.clone() {
+            args
+        } else {
+            panic!("Expected function type, got {}", method_t)
+        };
+
+        let obj_ent = self.cast_to_expected_type(obj_ent, method_arg_types[0].as_ref().clone());
+        args_with_self.push(obj_ent);
+
+        for (idx, arg) in args.iter().enumerate() {
+            let arg_ent = self.compile_expression(arg.clone());
+            let arg_ent = self.cast_to_expected_type(arg_ent, method_arg_types[idx+1].as_ref().clone());
+            args_with_self.push(arg_ent);
+        }
+
+        // call method, pass object reference as the 1st argument
+        let call_instr = InstructionKind::CallReference {
+            func: method_ent,
+            args: args_with_self
+        };
+        // function return type determines whether we store or forget the return value
+        // TODO: refactor to 1 method (same as for regular function)
+        let result_ent = match Self::get_function_return_type(&method_t) {
+            Type::Void => {
+                self.builder.push_instruction(call_instr.without_result());
+                // typechecker guarantees we don't use this so just return a placeholder
+                Entity::Null { uuid: 0, t: Type::Null }
+            }
+            t => {
+                let result_ent = self.function_context.new_register(t);
+                self.builder.push_instruction(call_instr.with_result(result_ent.clone()));
+                result_ent
+            }
+        };
+        if self.global_context.are_methods_checked() {
+            self.compile_exception_check();
+        }
+        result_ent
+    }
+
     /// perform cast if entity type is different than t
     fn cast_to_expected_type(&mut self, ent: Entity, t: Type) -> Entity {
         if ent.get_type() != t {
@@ -170,6 +1337,124 @@ impl FunctionCompiler {
         }
     }
 
+    /// compiles C-style for loop, initializer is compiled in the current block
+    fn compile_for_loop(
+        &mut self,
+        label: Option<String>,
+        init: Option<Box<Statement<TypeMeta>>>,
+        cond: Option<Box<Expression<TypeMeta>>>,
+        step: Option<Box<Statement<TypeMeta>>>,
+        stmt: Statement<TypeMeta>,
+    ) {
+        let suffix = self.global_context.new_label_suffix();
+        let cond_label = format!("__for_loop_cond__{}", suffix);
+        let body_label = format!("__for_loop_body__{}", suffix);
+        let step_label = format!("__for_loop_step__{}", suffix);
+        let end_label = format!("__for_loop_end__{}", suffix);
+
+        if let Some(init_stmt) = init {
+            self.compile_statement(*init_stmt);
+        }
+
+        // continue jumps to the step block
+        self.begin_loop(label, cond_label.clone(), end_label.clone(), step_label.clone());
+
+        // missing condition is always true
+        let cond_ent = match cond {
+            Some(cond_expr) => self.compile_expression(*cond_expr),
+            None => Entity::Bool { v: true, uuid: 0 },
+        };
+        let loop_cond_jump = InstructionKind::JumpCond {
+            cond: cond_ent,
+            true_label: body_label.clone(),
+            false_label: end_label,
+        };
+        self.builder.push_instruction(loop_cond_jump.without_result());
+        self.add_loop_exit();
+
+        // compile loop body, that jumps to the step block at the end
+        self.next_block(body_label);
+        self.compile_statement(stmt);
+        if !self.builder.block_is_terminated() {
+            self.compile_loop_jump(&None, true);
+        }
+
+        // step block is reachable only if the body doesn't always exit the loop
+        let mut back_edges = Vec::new();
+        if self.begin_continue_block(step_label) {
+            if let Some(step_stmt) = step {
+                self.compile_statement(*step_stmt);
+            }
+            let cond_jump = InstructionKind::Jump { label: cond_label };
+            self.builder.push_instruction(cond_jump.without_result());
+            back_edges.push((self.builder.get_block_label(), self.block_context.get_env_view()));
+        }
+        self.complete_loop(back_edges);
+    }
+
+    /// integer (or character) switch is dense if at least half of the values between the lowest and highest case
+    /// are used as case values, such switch is compiled to LLVM switch instead of a chain of comparisons
+    fn is_dense_switch(case_targets: &[(Expression<TypeMeta>, String)]) -> bool {
+        let vals: Vec<i64> = case_targets.iter()
+            .filter_map(|(val, _)| match val.item {
+                ExpressionKind::LitInt { val } => Some(val as i64),
+                ExpressionKind::LitChar { val } => Some(val as i64),
+                _ => None,
+            })
+            .collect();
+        match (vals.iter().min(), vals.iter().max()) {
+            (Some(min), Some(max)) => max - min < 2 * vals.len() as i64,
+            _ => false,
+        }
+    }
+
+    /// compiles comparison of the switch value with a single case value, returns boolean entity
+    fn compile_switch_comparison(&mut self, switch_ent: Entity, val: Expression<TypeMeta>) -> Entity {
+        let val_ent = self.compile_expression(val);
+        self.compile_binary_operation(BinaryOperator::Equal, switch_ent, val_ent, Type::Bool)
+    }
+
+    /// expression is side-effect-free if evaluating it cannot change the program state or fail,
+    /// such expressions can be evaluated eagerly (eg. in both branches of conditional expression)
+    fn is_side_effect_free(expr: &Expression<TypeMeta>) -> bool {
+        match &expr.item {
+            ExpressionKind::LitInt { .. }
+            | ExpressionKind::LitBool { .. }
+            | ExpressionKind::LitChar { .. }
+            | ExpressionKind::LitLong { .. }
+            | ExpressionKind::LitDouble { .. }
+            | ExpressionKind::LitStr { .. }
+            | ExpressionKind::LitNull => true,
+            ExpressionKind::Cast { expr, .. } => Self::is_side_effect_free(expr),
+            ExpressionKind::Reference { r } => matches!(
+                &r.item,
+                ReferenceKind::Ident { .. } | ReferenceKind::ObjectSelf { .. }
+            ),
+            ExpressionKind::Unary { arg, .. } => Self::is_side_effect_free(arg),
+            ExpressionKind::Binary { left, op, right } => {
+                // lazy operators create basic blocks, division can fail and concatenation allocates
+                let is_safe_op = match op {
+                    BinaryOperator::And
+                    | BinaryOperator::Or
+                    | BinaryOperator::Divide
+                    | BinaryOperator::Modulo => false,
+                    BinaryOperator::Plus => left.get_type() != Type::Str,
+                    _ => true,
+                };
+                is_safe_op && Self::is_side_effect_free(left) && Self::is_side_effect_free(right)
+            }
+            _ => false,
+        }
+    }
+
+    /// converts result of one of the conditional expression branches to the type of entire expression
+    fn cast_to_branch_type(&mut self, ent: Entity, t: Type) -> Entity {
+        match ent {
+            Entity::Null { uuid, .. } => Entity::Null { uuid, t },
+            ent => self.cast_to_expected_type(ent, t),
+        }
+    }
+
     pub fn compile_expression(&mut self, expr: Expression<TypeMeta>) -> Entity {
         let result_t = expr.get_type();
         match expr.item {
@@ -185,6 +1470,27 @@ impl FunctionCompiler {
                     uuid: self.function_context.new_uuid(),
                 }
             }
+            ExpressionKind::LitChar { val } => {
+                Entity::Char {
+                    v: val,
+                    uuid: self.function_context.new_uuid(),
+                }
+            }
+            ExpressionKind::LitLong { val } => {
+                Entity::Long {
+                    v: val,
+                    uuid: self.function_context.new_uuid(),
+                }
+            }
+            ExpressionKind::LitDouble { val } => {
+                Entity::Double {
+                    v: val.to_bits(),
+                    uuid: self.function_context.new_uuid(),
+                }
+            }
+            ExpressionKind::Interpolation { parts, exprs } => {
+                self.compile_interpolation(parts, exprs.into_iter().map(|expr| *expr).collect())
+            }
             ExpressionKind::LitStr { val } => {
                 // declare the string as global constant
                 let string_decl = self.global_context.declare_string(val);
@@ -214,12 +1520,21 @@ impl FunctionCompiler {
                 }
             }
             ExpressionKind::App { r, args } => {
+                let args: Vec<Expression<TypeMeta>> = args.into_iter().map(|arg| *arg).collect();
                 match &r.item {
-                    ReferenceKind::Ident { ident } => {
-                        let func_name = self.global_context.get_function_name(&ident);
+                    ReferenceKind::Builtin { ident } if matches!(r.get_type(), Type::VariadicFunction { .. }) => {
+                        self.compile_print(ident, args)
+                    }
+                    ReferenceKind::Ident { ident } | ReferenceKind::Builtin { ident } => {
+                        let is_builtin = matches!(r.item, ReferenceKind::Builtin { .. });
+                        let func_name = if is_builtin {
+                            self.global_context.get_native_function_name(ident)
+                        } else {
+                            self.global_context.get_function_name(ident)
+                        };
                         // compile argument expressions  TODO: Casting
-                        let arg_entities: Vec<Entity> = args.iter()
-                            .map(|a| self.compile_expression(*a.clone()))
+                        let arg_entities: Vec<Entity> = args.into_iter()
+                            .map(|a| self.compile_expression(a))
                             .collect();
 
                         // compile actual call instruction
@@ -229,7 +1544,7 @@ impl FunctionCompiler {
                         };
 
                         // function return type determines whether we store or forget the return value
-                        match Self::get_function_return_type(&r.get_type()) {
+                        let result_ent = match Self::get_function_return_type(&r.get_type()) {
                             Type::Void => {
                                 self.builder.push_instruction(instr.without_result());
                                 // typechecker guarantees we don't use this so just return a placeholder
@@ -240,93 +1555,21 @@ impl FunctionCompiler {
                                 self.builder.push_instruction(instr.with_result(result_ent.clone()));
                                 result_ent
                             }
+                        };
+                        if !is_builtin && self.global_context.is_function_checked(ident) {
+                            self.compile_exception_check();
                         }
+                        result_ent
                     },
+                    ReferenceKind::ListMethod { ident, method, self_cls } => {
+                        self.compile_list_method(ident, self_cls, method, args)
+                    }
+                    ReferenceKind::MapMethod { ident, method, self_cls } => {
+                        self.compile_map_method(ident, self_cls, method, args)
+                    }
                     ReferenceKind::TypedObject { obj, cls, field } => {
-                        let struct_decl = self.global_context.get_struct_decl(cls);
-                        let obj_ent = self.block_context.get_variable(obj);
-
-                        let vtable_t = Type::BuiltinClass {
-                            ident: self.global_context.vtable_struct_name(cls)
-                        };
-                        let vtable_constant_name = Entity::GlobalConst {
-                            name: self.global_context.vtable_struct_const(cls),
-                            t: vtable_t.clone()
-                        };
-
-                        // get vtable reference
-                        let vtable_gep_instr = InstructionKind::GetStructElementPtr {
-                            container_type_name: struct_decl.llvm_name(),
-                            var: obj_ent.clone(),
-                            idx: Entity::Int { v: 0, uuid: 0 }
-                        };
-                        let vtable_gep_reg = self.function_context.new_register(vtable_t.reference());
-                        self.builder.push_instruction(vtable_gep_instr.with_result(vtable_gep_reg.clone()));
-
-                        // load vtable
-                        let vtable_load_instr = InstructionKind::Load {
-                            ptr: vtable_gep_reg
-                        };
-                        let vtable_ent = self.function_context.new_register(vtable_t.clone());
-                        self.builder.push_instruction(vtable_load_instr.with_result(vtable_ent.clone()));
-
-                        // get reference to method from vtable
-                        let vtable_struct = self.global_context.get_vtable_decl(cls);
-                        let method_idx = vtable_struct.method_env.get(field).unwrap();
-                        let method_gep_instr = InstructionKind::GetStructElementPtr {
-                            container_type_name: vtable_struct.llvm_name(),
-                            var: vtable_ent,
-                            idx: Entity::Int { v: *method_idx, uuid: 0 }
-                        };
-                        let method_t = vtable_struct.methods[*method_idx as usize].0.clone();
-                        let method_ptr = self.function_context.new_register(method_t.reference().reference());
-                        self.builder.push_instruction(method_gep_instr.with_result(method_ptr.clone()));
-
-                        // load method
-                        let method_load_instr = InstructionKind::Load {
-                            ptr: method_ptr
-                        };
-                        let method_ent = self.function_context.new_register(method_t.reference());
-                        self.builder.push_instruction(method_load_instr.with_result(method_ent.clone()));
-
-                        // build args: self, compiled results of passed expressions
-                        let mut args_with_self = Vec::new();
-
-                        // TODO: Refactor, this was also repeated in ClassCompiler
-                        let method_arg_types = if let Type::Function { args, ret } = method_t.clone() {
-                            args
-                        } else {
-                            panic!("Expected function type, got {}", method_t)
-                        };
-
-                        let obj_ent = self.cast_to_expected_type(obj_ent, method_arg_types[0].as_ref().clone());
-                        args_with_self.push(obj_ent);
-
-                        for (idx, arg) in args.iter().enumerate() {
-                            let arg_ent = self.compile_expression(arg.as_ref().clone());
-                            let arg_ent = self.cast_to_expected_type(arg_ent, method_arg_types[idx+1].as_ref().clone());
-                            args_with_self.push(arg_ent);
-                        }
-
-                        // call method, pass object reference as the 1st argument
-                        let call_instr = InstructionKind::CallReference {
-                            func: method_ent,
-                            args: args_with_self
-                        };
-                        // function return type determines whether we store or forget the return value
-                        // TODO: refactor to 1 method (same as for regular function)
-                        match Self::get_function_return_type(&method_t) {
-                            Type::Void => {
-                                self.builder.push_instruction(call_instr.without_result());
-                                // typechecker guarantees we don't use this so just return a placeholder
-                                Entity::Null { uuid: 0, t: Type::Null }
-                            }
-                            t => {
-                                let result_ent = self.function_context.new_register(t);
-                                self.builder.push_instruction(call_instr.with_result(result_ent.clone()));
-                                result_ent
-                            }
-                        }
+                        let obj_ent = self.get_variable(obj);
+                        self.compile_method_call(obj_ent, cls, field, args)
                     }
                     ReferenceKind::TypedMemberObject { self_cls, obj, obj_class, field } => {
                         let obj_type = Type::Class { ident: self_cls.clone() };
@@ -343,89 +1586,7 @@ impl FunctionCompiler {
                         let obj_ent = self.compile_expression(Expression::new(expr_kind, TypeMeta { t: obj_type.clone() }));
 
                         // now we can perform actual call, in the same way as for TypedObject
-                        let struct_decl = self.global_context.get_struct_decl(obj_class);
-
-                        let vtable_t = Type::BuiltinClass {
-                            ident: self.global_context.vtable_struct_name(obj_class)
-                        };
-                        let vtable_constant_name = Entity::GlobalConst {
-                            name: self.global_context.vtable_struct_const(obj_class),
-                            t: vtable_t.clone()
-                        };
-
-                        // get vtable reference
-                        let vtable_gep_instr = InstructionKind::GetStructElementPtr {
-                            container_type_name: struct_decl.llvm_name(),
-                            var: obj_ent.clone(),
-                            idx: Entity::Int { v: 0, uuid: 0 }
-                        };
-                        let vtable_gep_reg = self.function_context.new_register(vtable_t.reference());
-                        self.builder.push_instruction(vtable_gep_instr.with_result(vtable_gep_reg.clone()));
-
-                        // load vtable
-                        let vtable_load_instr = InstructionKind::Load {
-                            ptr: vtable_gep_reg
-                        };
-                        let vtable_ent = self.function_context.new_register(vtable_t.clone());
-                        self.builder.push_instruction(vtable_load_instr.with_result(vtable_ent.clone()));
-
-                        // get reference to method from vtable
-                        let vtable_struct = self.global_context.get_vtable_decl(obj_class);
-                        let method_idx = vtable_struct.method_env.get(field).unwrap();
-                        let method_gep_instr = InstructionKind::GetStructElementPtr {
-                            container_type_name: vtable_struct.llvm_name(),
-                            var: vtable_ent,
-                            idx: Entity::Int { v: *method_idx, uuid: 0 }
-                        };
-                        let method_t = vtable_struct.methods[*method_idx as usize].0.clone();
-                        let method_ptr = self.function_context.new_register(method_t.reference().reference());
-                        self.builder.push_instruction(method_gep_instr.with_result(method_ptr.clone()));
-
-                        // load method
-                        let method_load_instr = InstructionKind::Load {
-                            ptr: method_ptr
-                        };
-                        let method_ent = self.function_context.new_register(method_t.reference());
-                        self.builder.push_instruction(method_load_instr.with_result(method_ent.clone()));
-
-                        // build args: self, compiled results of passed expressions
-                        let mut args_with_self = Vec::new();
-
-                        // TODO: Refactor, this was also repeated in ClassCompiler
-                        let method_arg_types = if let Type::Function { args, ret } = method_t.clone() {
-                            args
-                        } else {
-                            panic!("Expected function type, got {}", method_t)
-                        };
-
-                        let obj_ent = self.cast_to_expected_type(obj_ent, method_arg_types[0].as_ref().clone());
-                        args_with_self.push(obj_ent);
-
-                        for (idx, arg) in args.iter().enumerate() {
-                            let arg_ent = self.compile_expression(arg.as_ref().clone());
-                            let arg_ent = self.cast_to_expected_type(arg_ent, method_arg_types[idx+1].as_ref().clone());
-                            args_with_self.push(arg_ent);
-                        }
-
-                        // call method, pass object reference as the 1st argument
-                        let call_instr = InstructionKind::CallReference {
-                            func: method_ent,
-                            args: args_with_self
-                        };
-                        // function return type determines whether we store or forget the return value
-                        // TODO: refactor to 1 method (same as for regular function)
-                        match Self::get_function_return_type(&method_t) {
-                            Type::Void => {
-                                self.builder.push_instruction(call_instr.without_result());
-                                // typechecker guarantees we don't use this so just return a placeholder
-                                Entity::Null { uuid: 0, t: Type::Null }
-                            }
-                            t => {
-                                let result_ent = self.function_context.new_register(t);
-                                self.builder.push_instruction(call_instr.with_result(result_ent.clone()));
-                                result_ent
-                            }
-                        }
+                        self.compile_method_call(obj_ent, obj_class, field, args)
                     }
                     r => {
                         // all self-references are mapped to typed object references with "self" variable
@@ -490,8 +1651,12 @@ impl FunctionCompiler {
                     let cont_env = self.block_context.get_env_view();
                     let cont_label = self.builder.get_block_label();
 
-                    // at the end, load the value of the result back to a register
-                    self.next_block(end_label);
+                    // at the end, load the value of the result back to a register,
+                    // variables could have been modified in the right branch (eg. by increment)
+                    self.join_blocks(end_label, vec![
+                        (in_label.clone(), in_env),
+                        (cont_label.clone(), cont_env),
+                    ]);
                     let phi_instr = InstructionKind::Phi {
                         args: vec![(left_ent, in_label), (right_ent, cont_label)]
                     };
@@ -500,138 +1665,125 @@ impl FunctionCompiler {
                     phi_reg
                 } else {
                     // evaluate both sides of expression before performing the operation
-                    let t = left.get_type();
                     let left_ent = self.compile_expression(*left);
                     let right_ent = self.compile_expression(*right);
 
-                    // for strings, use concatenation function instead of llvm operator
-                    let instr = if t == Type::Str && op == BinaryOperator::Plus {
-                        InstructionKind::Call {
-                            func: String::from("__builtin_method__str__concat__"),
-                            args: vec![left_ent, right_ent],
-                        }
-                    } else {
-                        InstructionKind::BinaryOp {
-                            op: op.clone(),
-                            l: left_ent,
-                            r: right_ent,
-                        }
-                    };
-                    let result_ent = self.function_context.new_register(result_t);
-                    self.builder.push_instruction(instr.with_result(result_ent.clone()));
-                    result_ent
-                }
+                    self.compile_binary_operation(op, left_ent, right_ent, result_t)
+                }
+            }
+            ExpressionKind::Cond { cond, expr_true, expr_false } => {
+                let cond_ent = self.compile_expression(*cond);
+
+                if Self::is_side_effect_free(&expr_true) && Self::is_side_effect_free(&expr_false) {
+                    // both values can be calculated without branching
+                    let true_ent = self.compile_expression(*expr_true);
+                    let true_ent = self.cast_to_branch_type(true_ent, result_t.clone());
+                    let false_ent = self.compile_expression(*expr_false);
+                    let false_ent = self.cast_to_branch_type(false_ent, result_t.clone());
+                    let select_instr = InstructionKind::Select {
+                        cond: cond_ent,
+                        val_true: true_ent,
+                        val_false: false_ent,
+                    };
+                    let result_reg = self.function_context.new_register(result_t);
+                    self.builder.push_instruction(select_instr.with_result(result_reg.clone()));
+                    return result_reg;
+                }
+
+                // generate labels for conditional jump
+                let suffix = self.global_context.new_label_suffix();
+                let true_label = format!("__cond_expr__true__{}", suffix);
+                let false_label = format!("__cond_expr__false__{}", suffix);
+                let end_label = format!("__cond_expr__end__{}", suffix);
+
+                let cond_jump = InstructionKind::JumpCond {
+                    cond: cond_ent,
+                    true_label: true_label.clone(),
+                    false_label: false_label.clone(),
+                };
+                self.builder.push_instruction(cond_jump.without_result());
+                let in_env = self.block_context.get_env_view();
+                let end_jump = InstructionKind::Jump { label: end_label.clone() };
+
+                // true branch, its end label may differ from the beginning (eg. nested expressions)
+                self.next_block(true_label);
+                let true_ent = self.compile_expression(*expr_true);
+                let true_ent = self.cast_to_branch_type(true_ent, result_t.clone());
+                self.builder.push_instruction(end_jump.clone().without_result());
+                let true_label = self.builder.get_block_label();
+                let true_env = self.block_context.get_env_view();
+
+                // false branch starts with the same variable values as the true one
+                self.block_context.restore_env(&in_env);
+                self.next_block(false_label);
+                let false_ent = self.compile_expression(*expr_false);
+                let false_ent = self.cast_to_branch_type(false_ent, result_t.clone());
+                self.builder.push_instruction(end_jump.without_result());
+                let false_label = self.builder.get_block_label();
+                let false_env = self.block_context.get_env_view();
+
+                // variables could have been modified in the branches (eg. by increment)
+                self.join_blocks(end_label, vec![
+                    (true_label.clone(), true_env),
+                    (false_label.clone(), false_env),
+                ]);
+                let phi_instr = InstructionKind::Phi {
+                    args: vec![(true_ent, true_label), (false_ent, false_label)]
+                };
+                let phi_reg = self.function_context.new_register(result_t);
+                self.builder.push_instruction(phi_instr.with_result(phi_reg.clone()));
+                phi_reg
             }
             ExpressionKind::InitDefault { t } => {
                 if let Type::Class { ident } = t {
-                    let instr = InstructionKind::Call {
-                        func: self.global_context.get_init_name(&ident),
-                        args: vec![],
-                    };
-                    let result_ent = self.function_context.new_register(result_t);
-                    self.builder.push_instruction(instr.with_result(result_ent.clone()));
-                    result_ent
+                    self.compile_object_init(&ident, result_t)
+                } else if let Type::List { .. } = t {
+                    self.compile_list_init(t)
+                } else if let Type::Map { .. } = t {
+                    self.compile_map_init(t)
                 } else {
                     panic!("Invalid type {:?} for Expression::InitDefault", t)
                 }
             }
             ExpressionKind::InitArr { t, size } => {
-                let void_ptr_t = Type::Str; // apparently void* in C is i8* in LLVM
-                let arr_item_t = Self::get_array_item_type(&t);
-
-                // register new array type that has to be declared for our program
-                let arr_struct_decl = self.global_context.get_or_declare_array_struct(&arr_item_t);
-
                 // calculate length (number of items)
                 let length_ent = self.compile_expression(*size);
 
-                // calculate size of the array (in bytes)
-                let item_size_ent = Self::get_size(&t);
-                let byte_count_instr = InstructionKind::BinaryOp {
-                    op: BinaryOperator::Times,
-                    l: length_ent.clone(),
-                    r: item_size_ent
-                };
-                let bytes_size_ent = self.function_context.new_register(Type::Int);
-                self.builder.push_instruction(byte_count_instr.with_result(bytes_size_ent.clone()));
-
-                // allocate memory for the array
-                let raw_array_malloc = InstructionKind::Call {
-                    func: String::from("__builtin_method__array__init__"),
-                    args: vec![bytes_size_ent]
-                };
-                let malloc_result_ent = self.function_context.new_register(void_ptr_t.clone());
-                self.builder.push_instruction(raw_array_malloc.with_result(malloc_result_ent.clone()));
-
-                // cast raw array to its appropriate type
-                let raw_array_t = Type::Reference { t: Box::new(arr_item_t.clone()) };
-                let raw_array_cast = InstructionKind::BitCast {
-                    ent: malloc_result_ent,
-                    to: raw_array_t.clone()
-                };
-                let raw_array_ent = self.function_context.new_register(raw_array_t.clone());
-                self.builder.push_instruction(raw_array_cast.with_result(raw_array_ent.clone()));
-
-                // load size of array struct
-                let load_struct_size = InstructionKind::Load {
-                    ptr: Entity::GlobalConst { name: self.global_context.get_array_struct_size_name(&arr_item_t), t: Type::Int.reference() }
-                };
-                let struct_size_ent = self.function_context.new_register(Type::Int);
-                self.builder.push_instruction(load_struct_size.with_result(struct_size_ent.clone()));
-
-                // use array init to allocate array struct as well
-                let struct_init = InstructionKind::Call {
-                    func: String::from("__builtin_method__array__init__"),
-                    args: vec![struct_size_ent]
-                };
-                let array_init_ent = self.function_context.new_register(void_ptr_t);
-                self.builder.push_instruction(struct_init.with_result(array_init_ent.clone()));
-
-                // cast the result to appropriate type
-                let array_struct_cast = InstructionKind::BitCast {
-                    ent: array_init_ent,
-                    to: t.clone()
-                };
-                let array_struct_ptr_ent = self.function_context.new_register(t.clone());
-                self.builder.push_instruction(array_struct_cast.with_result(array_struct_ptr_ent.clone()));
-
-                // set length
-                let length_gep = InstructionKind::GetStructElementPtr {
-                    container_type_name: arr_struct_decl.llvm_name(),
-                    var: array_struct_ptr_ent.clone(),
-                    idx: Entity::Int { v: 0, uuid: 0 }
-                };
-                let length_ptr = self.function_context.new_register(Type::Int.reference());
-                self.builder.push_instruction(length_gep.with_result(length_ptr.clone()));
-
-                let store_length = InstructionKind::Store {
-                    val: length_ent,
-                    ptr: length_ptr
-                };
-                self.builder.push_instruction(store_length.without_result());
-
-                // move the allocated raw array into array struct
-                let raw_array_gep = InstructionKind::GetStructElementPtr {
-                    container_type_name: arr_struct_decl.llvm_name(),
-                    var: array_struct_ptr_ent.clone(),
-                    idx: Entity::Int { v: 1, uuid: 0 }
-                };
-                let raw_array_ptr = self.function_context.new_register(raw_array_t.reference());
-                self.builder.push_instruction(raw_array_gep.with_result(raw_array_ptr.clone()));
+                // return array struct: {length, array}
+                let (array_struct_ptr_ent, _) = self.compile_array_allocation(t, length_ent);
+                array_struct_ptr_ent
+            }
+            ExpressionKind::LitArr { t, items } => {
+                let arr_item_t = Self::get_array_item_type(&t);
+                let length_ent = Entity::Int { v: items.len() as i32, uuid: 0 };
+                let (array_struct_ptr_ent, raw_array_ent) = self.compile_array_allocation(t, length_ent);
+
+                // evaluate items in order, storing each one directly in the raw array
+                for (idx, item) in items.into_iter().enumerate() {
+                    let item_ent = self.compile_expression(*item);
+                    let item_ent = self.cast_to_expected_type(item_ent, arr_item_t.clone());
+
+                    let get_item_ptr = InstructionKind::GetArrayElementPtr {
+                        item_t: arr_item_t.clone(),
+                        var: raw_array_ent.clone(),
+                        idx: Entity::Int { v: idx as i32, uuid: 0 }
+                    };
+                    let item_ptr_ent = self.function_context.new_register(arr_item_t.reference());
+                    self.builder.push_instruction(get_item_ptr.with_result(item_ptr_ent.clone()));
 
-                let store_array = InstructionKind::Store {
-                    val: raw_array_ent,
-                    ptr: raw_array_ptr
-                };
-                self.builder.push_instruction(store_array.without_result());
+                    let store_item = InstructionKind::Store {
+                        val: item_ent,
+                        ptr: item_ptr_ent
+                    };
+                    self.builder.push_instruction(store_item.without_result());
+                }
 
-                // return array struct: {length, array}
                 array_struct_ptr_ent
             }
             ExpressionKind::Reference { r } => {
                 match &r.item {
                     ReferenceKind::Ident { ident } => {
-                        self.block_context.get_variable(ident)
+                        self.get_variable(ident)
                     }
                     ReferenceKind::TypedObject { obj, cls, field } => {
                         let struct_decl = self.global_context.get_struct_decl(cls);
@@ -639,7 +1791,7 @@ impl FunctionCompiler {
                         let field_idx = struct_decl.field_env.get(field).unwrap();
                         let field_t: &Type = struct_decl.fields.get(*field_idx as usize).unwrap();
 
-                        let obj_ent = self.block_context.get_variable(obj);
+                        let obj_ent = self.get_variable(obj);
 
                         let gep_instr = InstructionKind::GetStructElementPtr {
                             container_type_name: struct_decl.llvm_name(),
@@ -696,7 +1848,7 @@ impl FunctionCompiler {
                         )
                     }
                     ReferenceKind::Array { arr, idx } => {
-                        let array_ent = self.block_context.get_variable(arr);
+                        let array_ent = self.get_variable(arr);
                         let idx_ent = self.compile_expression(idx.as_ref().clone());
                         let gep_reg = self.compile_array_gep(
                             array_ent.get_array_item_t(),
@@ -715,8 +1867,13 @@ impl FunctionCompiler {
                         // by the typechecker
                         unreachable!()
                     }
+                    ReferenceKind::ListMethod { .. } | ReferenceKind::MapMethod { .. } | ReferenceKind::Builtin { .. } => {
+                        // list methods, map methods and builtin functions can only be called,
+                        // typechecker maps them only in App
+                        unreachable!()
+                    }
                     ReferenceKind::ArrayLen { ident } => {
-                        let obj_ent = self.block_context.get_variable(ident);
+                        let obj_ent = self.get_variable(ident);
                         let arr_item_t = Self::get_array_item_type(&obj_ent.get_type());
 
                         let gep_instr = InstructionKind::GetStructElementPtr {
@@ -733,8 +1890,40 @@ impl FunctionCompiler {
 
                         load_reg
                     }
+                    ReferenceKind::StrLen { ident, self_cls } => {
+                        let str_ent = self.get_variable_or_member(ident, self_cls);
+                        let instr = InstructionKind::Call {
+                            func: String::from("__builtin_method__str__length__"),
+                            args: vec![str_ent],
+                        };
+                        let result_reg = self.function_context.new_register(Type::Int);
+                        self.builder.push_instruction(instr.with_result(result_reg.clone()));
+                        result_reg
+                    }
+                    ReferenceKind::StrChar { ident, idx, self_cls } => {
+                        // indexing is equivalent to calling the charAt builtin function
+                        let str_ent = self.get_variable_or_member(ident, self_cls);
+                        let idx_ent = self.compile_expression(idx.as_ref().clone());
+                        let instr = InstructionKind::Call {
+                            func: self.global_context.get_native_function_name(&String::from("charAt")),
+                            args: vec![str_ent, idx_ent],
+                        };
+                        let result_reg = self.function_context.new_register(result_t.clone());
+                        self.builder.push_instruction(instr.with_result(result_reg.clone()));
+                        result_reg
+                    }
                 }
             }
+            ExpressionKind::Mut { r, op, prefix } => {
+                let location = self.compile_reference_location(&r);
+                let old_ent = self.load_reference_location(&location);
+                let t = r.get_type();
+                let new_ent = self.compile_binary_operation(
+                    Self::get_mut_operator(&op), old_ent.clone(), Self::get_integral_constant(&t, 1), t
+                );
+                self.store_reference_location(&location, new_ent.clone());
+                if prefix { new_ent } else { old_ent }
+            }
             ExpressionKind::Cast { t, expr } => {
                 if let Entity::Null { uuid, t: _ } = self.compile_expression(*expr) {
                     Entity::Null { uuid, t }
@@ -755,54 +1944,21 @@ impl FunctionCompiler {
                 // use nested compiler to visit all statements in block, combine llvm results
                 let mut compiler = self.nested_for_block();
                 for stmt in block.item.stmts {
+                    if compiler.builder.block_is_terminated() {
+                        // statements after return, break or continue are unreachable
+                        break;
+                    }
                     compiler.compile_statement(*stmt)
                 }
                 self.merge_block_compiler(compiler);
             }
             StatementKind::Empty => {}
-            StatementKind::Decl { t, items } => {
+            StatementKind::Decl { t, items, .. } => {
                 for item in items {
                     // get identifier and entity representing the value (const with default if not provided)
                     let (entity, ident) = match item.item {
                         DeclItemKind::NoInit { ident } => {
-                            let result_t = t.clone();
-                            let entity = match &t {
-                                Type::Int => Entity::Int {
-                                    v: 0,
-                                    uuid: self.function_context.new_uuid(),
-                                },
-                                Type::Bool => Entity::Bool {
-                                    v: false,
-                                    uuid: self.function_context.new_uuid(),
-                                },
-                                Type::Str => {
-                                    let default_init = InstructionKind::Call {
-                                        func: String::from("__builtin_method__str__init__"),
-                                        args: vec![Entity::Int { v: 0, uuid: 0 }],
-                                    };
-                                    let call_ret_ent = self.function_context.new_register(Type::Str);
-                                    self.builder.push_instruction(
-                                        default_init.with_result(call_ret_ent.clone())
-                                    );
-                                    call_ret_ent
-                                }
-                                Type::Class { ident } => {
-                                    let call_instr = InstructionKind::Call {
-                                        func: self.global_context.get_init_name(&ident),
-                                        args: vec![],
-                                    };
-                                    let call_result_ent = self.function_context.new_register(result_t);
-                                    self.builder.push_instruction(call_instr.with_result(call_result_ent.clone()));
-                                    call_result_ent
-                                }
-                                Type::Array { item_t } => {
-                                    Entity::Null {
-                                        uuid: self.function_context.new_uuid(),
-                                        t: Type::Array { item_t: item_t.clone() }
-                                    }
-                                }
-                                _ => unreachable!(),
-                            };
+                            let entity = self.compile_default_init(&t);
                             (entity, ident)
                         }
                         DeclItemKind::Init { ident, val } => {
@@ -827,15 +1983,13 @@ impl FunctionCompiler {
                 // get entity with a pointer to the referenced variable TODO: Refactor-out
                 match &r.item {
                     ReferenceKind::Ident { ident } => {
-                        // update local variable environment to reflect the change
-                        let entity = self.make_unique_entity(original_ent);
-                        self.block_context.update_variable(ident.clone(), entity);
+                        self.set_variable(ident, original_ent);
                     }
                     ReferenceKind::TypedObject { obj, cls, field } => {
                         let struct_decl = self.global_context.get_struct_decl(cls);
                         let field_idx = struct_decl.field_env.get(field).unwrap();
 
-                        let obj_ent = self.block_context.get_variable(obj);
+                        let obj_ent = self.get_variable(obj);
 
                         // get pointer to the struct member
                         let ptr_t = r.get_type().reference();
@@ -857,7 +2011,7 @@ impl FunctionCompiler {
                     ReferenceKind::Array { arr, idx } => {
                         // compile necessary getelementptr instructions
                         let idx_ent = self.compile_expression(idx.as_ref().clone());
-                        let array_ent = self.block_context.get_variable(arr);
+                        let array_ent = self.get_variable(arr);
                         let gep_reg = self.compile_array_gep(
                             array_ent.get_array_item_t(),
                             array_ent,
@@ -875,91 +2029,39 @@ impl FunctionCompiler {
                 }
             }
             StatementKind::Mut { r, op } => {
-                let var_ent = match &r.item {
-                    ReferenceKind::Ident { ident } => {
-                        self.block_context.get_variable(&ident)
-                    },
-                    ReferenceKind::TypedObject { obj, cls, field } => {
-                        // TODO: This is repeated way too many times, make a function
-                        let struct_decl = self.global_context.get_struct_decl(cls);
-                        let field_idx = *struct_decl.field_env.get(field).unwrap();
-                        let field_t = struct_decl.fields[field_idx as usize].clone();
-
-                        let obj_ent = self.block_context.get_variable(obj);
-
-                        let gep_instr = InstructionKind::GetStructElementPtr {
-                            container_type_name: struct_decl.llvm_name(),
-                            var: obj_ent,
-                            idx: Entity::Int { v: field_idx, uuid: 0 }
-                        };
-                        let gep_reg = self.function_context.new_register(field_t.reference());
-                        self.builder.push_instruction(gep_instr.with_result(gep_reg.clone()));
-
-                        let load_reg = self.function_context.new_register(field_t.clone());
-                        let load_instr = InstructionKind::Load { ptr: gep_reg };
-                        self.builder.push_instruction(load_instr.with_result(load_reg.clone()));
-
-                        load_reg
-                    },
-                    // arrays may also be interesting, but are not supported at the moment
-                    t => unreachable!(),
-                };
-
                 // perform the op on the extracted value
-                let binary_op = match op {
-                    StatementOp::Increment => BinaryOperator::Plus,
-                    StatementOp::Decrement => BinaryOperator::Minus,
-                };
-                let mut_op = InstructionKind::BinaryOp {
-                    op: binary_op,
-                    l: var_ent,
-                    r: Entity::Int { v: 1, uuid: 0 },
-                };
-                let mut_result_ent = self.function_context.new_register(Type::Int);
-                self.builder.push_instruction(mut_op.with_result(mut_result_ent.clone()));
-
-                // update local variable environment to reflect the change
-                match &r.item {
-                    ReferenceKind::Ident { ident } => {
-                        self.block_context.update_variable(ident.clone(), mut_result_ent);
-                    },
-                    ReferenceKind::TypedObject { obj, cls, field } => {
-                        // TODO: This is repeated way too many times, make a function
-                        let struct_decl = self.global_context.get_struct_decl(cls);
-                        let field_idx = *struct_decl.field_env.get(field).unwrap();
-                        let field_t = struct_decl.fields[field_idx as usize].clone();
-
-                        let obj_ent = self.block_context.get_variable(obj);
-
-                        let gep_instr = InstructionKind::GetStructElementPtr {
-                            container_type_name: struct_decl.llvm_name(),
-                            var: obj_ent,
-                            idx: Entity::Int { v: field_idx, uuid: 0 }
-                        };
-                        let gep_reg = self.function_context.new_register(field_t.reference());
-                        self.builder.push_instruction(gep_instr.with_result(gep_reg.clone()));
-
-                        let store_instr = InstructionKind::Store { val: mut_result_ent, ptr: gep_reg };
-                        self.builder.push_instruction(store_instr.without_result())
-                    },
-                    // arrays may also be interesting, but are not supported at the moment
-                    t => unreachable!(),
-                };
+                let location = self.compile_reference_location(&r);
+                let var_ent = self.load_reference_location(&location);
+                let t = r.get_type();
+                let mut_result_ent = self.compile_binary_operation(
+                    Self::get_mut_operator(&op), var_ent, Self::get_integral_constant(&t, 1), t
+                );
+                self.store_reference_location(&location, mut_result_ent);
+            }
+            StatementKind::AssOp { r, op, expr } => {
+                // target location is evaluated only once, before the expression
+                let location = self.compile_reference_location(&r);
+                let var_ent = self.load_reference_location(&location);
+                let expr_ent = self.compile_expression(*expr);
+                let result_ent = self.compile_binary_operation(op, var_ent, expr_ent, r.get_type());
+                self.store_reference_location(&location, result_ent);
             }
             StatementKind::Return { expr } => {
-                match expr {
-                    None => {
-                        let ret = InstructionKind::RetVoid;
-                        self.builder.push_instruction(ret.without_result());
-                    }
+                let ret = match expr {
+                    None => InstructionKind::RetVoid,
                     Some(e) => {
                         // compile expression
                         let result_ent = self.compile_expression(*e);
 
                         // return the value from register containing expression result
-                        let ret = InstructionKind::RetVal { val: result_ent };
-                        self.builder.push_instruction(ret.without_result());
+                        InstructionKind::RetVal { val: result_ent }
                     }
+                };
+
+                // returned value is evaluated before executing finally blocks of all enclosing try statements
+                self.compile_finally_blocks(0);
+                if !self.builder.block_is_terminated() {
+                    self.builder.push_instruction(ret.without_result());
                 }
             }
             StatementKind::Cond { expr, stmt } => {
@@ -980,23 +2082,23 @@ impl FunctionCompiler {
                 // collect information for phi calculation
                 let in_label = self.builder.get_block_label();
                 let in_env = self.block_context.get_env_view();
+                let mut predecessors = vec![(in_label, in_env)];
 
                 // true branch
-                self.next_block(true_label.clone());
-                self.builder.add_predecessor(in_label.clone(), &in_env);
+                self.next_block(true_label);
                 self.compile_statement(*stmt);
-                if !self.builder.block_always_returns() {
+                if !self.builder.block_is_terminated() {
                     let jump_instr = InstructionKind::Jump { label: end_label.clone() };
                     self.builder.push_instruction(jump_instr.without_result());
-                }
 
-                // collect information for phi calculation
-                let true_env = self.block_context.get_env_view();
+                    // collect information for phi calculation, true branch may end in a different block
+                    let true_label = self.builder.get_block_label();
+                    let true_env = self.block_context.get_env_view();
+                    predecessors.push((true_label, true_env));
+                }
 
                 // end = new current block, with additional predecessor (in_block)
-                self.next_block(end_label);
-                self.builder.add_predecessor(in_label, &in_env);
-                self.builder.add_predecessor(true_label, &true_env);
+                self.join_blocks(end_label, predecessors);
             }
             StatementKind::CondElse { expr, stmt_true, stmt_false } => {
                 // create labels for all branches
@@ -1015,91 +2117,71 @@ impl FunctionCompiler {
                 self.builder.push_instruction(cond_jump_instr.without_result());
 
                 // collect information for phi calculation
-                let in_label = self.builder.get_block_label();
                 let in_env = self.block_context.get_env_view();
 
                 // prepare common jump instruction for both branches
                 let end_jump_instr = InstructionKind::Jump { label: end_label.clone() }
                     .without_result();
-                let mut next_block_necessary = false;
+                let mut predecessors = Vec::new();
 
                 // true branch
-                self.next_block(true_label.clone());
-                self.builder.add_predecessor(in_label.clone(), &in_env);
+                self.next_block(true_label);
                 self.compile_statement(*stmt_true);
-                if !self.builder.block_always_returns() {
+                if !self.builder.block_is_terminated() {
                     self.builder.push_instruction(end_jump_instr.clone());
-                    next_block_necessary = true;
+                    predecessors.push((self.builder.get_block_label(), self.block_context.get_env_view()));
                 }
 
-                // collect information for phi calculation
-                let true_env = self.block_context.get_env_view();
-
-                // false branch
-                self.next_block(false_label.clone());
-                self.builder.add_predecessor(in_label.clone(), &in_env);
+                // false branch starts with the same variable values as the true one
+                self.block_context.restore_env(&in_env);
+                self.next_block(false_label);
                 self.compile_statement(*stmt_false);
-                if !self.builder.block_always_returns() {
+                if !self.builder.block_is_terminated() {
                     self.builder.push_instruction(end_jump_instr.clone());
-                    next_block_necessary = true;
+                    predecessors.push((self.builder.get_block_label(), self.block_context.get_env_view()));
                 }
 
-                // collect information for phi calculation
-                let false_env = self.block_context.get_env_view();
-
                 // if either of the sides needed to jump to the end block, create it
-                if next_block_necessary {
-                    self.next_block(end_label);
-                    self.builder.add_predecessor(true_label, &true_env);
-                    self.builder.add_predecessor(false_label, &false_env);
+                if !predecessors.is_empty() {
+                    self.join_blocks(end_label, predecessors);
                 }
             }
-            StatementKind::While { expr, stmt } => {
+            StatementKind::While { label, expr, stmt } => {
                 // get labels with unique suffix
                 let suffix = self.global_context.new_label_suffix();
                 let cond_label = format!("__loop_cond__{}", suffix);
                 let loop_label = format!("__loop_body__{}", suffix);
                 let end_label = format!("__loop_end__{}", suffix);
 
-                // jump to condition evaluation sequence
-                let cond_jump = InstructionKind::Jump { label: cond_label.clone() }
-                    .without_result();
-                self.builder.push_instruction(cond_jump.clone());
-
-                // collect information for phi calculation
-                let in_label = self.builder.get_block_label();
-                let in_env = self.block_context.get_env_view();
-
-                // compile loop body, that also jumps back to cond at the end
-                self.next_block(loop_label.clone());
-                // we don't need to specify predecessor for body block
-                self.compile_statement(*stmt);
-                self.builder.push_instruction(cond_jump);
+                // condition is evaluated in the loop header, so continue jumps there
+                self.begin_loop(label, cond_label.clone(), end_label.clone(), cond_label);
 
-                // collect information for phi calculation
-                let body_env = self.block_context.get_env_view();
-
-                // begin next block by evaluating necessary phi statements based on block variables
-                self.next_block(cond_label);
-                self.builder.add_predecessor(in_label, &in_env);
-                self.builder.add_predecessor(loop_label.clone(), &body_env);
-
-                // evaluate condition in a new block and jump to loop body or end
+                // evaluate condition and jump to loop body or end
                 let expr_result = self.compile_expression(*expr);
                 let loop_cond_jump = InstructionKind::JumpCond {
                     cond: expr_result,
-                    true_label: loop_label,
-                    false_label: end_label.clone(),
+                    true_label: loop_label.clone(),
+                    false_label: end_label,
                 };
                 self.builder.push_instruction(loop_cond_jump.without_result());
+                self.add_loop_exit();
+
+                // compile loop body, that also jumps back to cond at the end
+                self.next_block(loop_label);
+                self.compile_statement(*stmt);
+                if !self.builder.block_is_terminated() {
+                    self.compile_loop_jump(&None, true);
+                }
 
-                // start new block with loop end label, and fix mapping in 2 previous blocks
-                self.complete_loop_block(end_label);
+                // all blocks jumping to the header are back-edges
+                let back_edges = self.loops.last_mut().unwrap().continue_predecessors.drain(..).collect();
+                self.complete_loop(back_edges);
             }
-            StatementKind::For { t, ident, arr, stmt } => {
+            StatementKind::For { label, t: _, ident, arr, stmt } => {
                 let suffix = self.global_context.new_label_suffix();
                 let cond_label = format!("__for_cond__{}", suffix);
                 let body_label = format!("__for_body__{}", suffix);
+                let step_label = format!("__for_step__{}", suffix);
                 let end_label = format!("__for_end__{}", suffix);
 
                 // create variable i (index), dot prefix prevents conflicts with user variables
@@ -1107,105 +2189,409 @@ impl FunctionCompiler {
                 let index_ent = Entity::Int { v: 0, uuid: self.function_context.new_uuid() };
                 self.block_context.set_new_variable(index_ident.clone(), index_ent.clone());
 
-                // create entity holding a pointer to the array
+                // create entity holding a pointer to the array, maps are iterated over a list of their keys
                 let array_ent = self.compile_expression(*arr);
+                let array_ent = if let Type::Map { .. } = array_ent.get_type() {
+                    self.compile_map_call("keys", array_ent, vec![])
+                } else {
+                    array_ent
+                };
                 let array_item_t = array_ent.get_array_item_t();
 
-                // jump to condition evaluation sequence
-                let cond_jump = InstructionKind::Jump { label: cond_label.clone() }
-                    .without_result();
-                self.builder.push_instruction(cond_jump.clone());
+                // continue jumps to the step block, where the index is incremented
+                self.begin_loop(label, cond_label.clone(), end_label.clone(), step_label.clone());
 
-                // collect information for phi calculation
-                let in_label = self.builder.get_block_label();
-                let in_env = self.block_context.get_env_view();
+                // get array length (list size is checked in every iteration, as it may change)
+                let is_list = matches!(array_ent.get_type(), Type::List { .. });
+                let length_load_reg = if is_list {
+                    let size_instr = InstructionKind::Call {
+                        func: String::from("__builtin_method__list__size__"),
+                        args: vec![array_ent.clone()]
+                    };
+                    let size_reg = self.function_context.new_register(Type::Int);
+                    self.builder.push_instruction(size_instr.with_result(size_reg.clone()));
+                    size_reg
+                } else {
+                    let length_gep_instr = InstructionKind::GetStructElementPtr {
+                        container_type_name: self.global_context.get_or_declare_array_struct(&array_item_t).llvm_name(),
+                        var: array_ent.clone(),
+                        idx: Entity::Int { v: 0, uuid: 0 }
+                    };
+                    let length_gep_reg = self.function_context.new_register(Type::Int.reference());
+                    self.builder.push_instruction(length_gep_instr.with_result(length_gep_reg.clone()));
 
-                // compile loop body, that also jumps back to cond at the end
-                // we don't need to specify predecessor for body block
-                self.next_block(body_label.clone());
-
-                // get pointer to the current item from an array
-                let gep_reg = self.compile_array_gep(
-                    array_item_t.clone(),
-                    array_ent.clone(),
-                    self.block_context.get_variable(&index_ident)
-                );
+                    let length_load_reg = self.function_context.new_register(Type::Int);
+                    let length_load_instr = InstructionKind::Load { ptr: length_gep_reg };
+                    self.builder.push_instruction(length_load_instr.with_result(length_load_reg.clone()));
+                    length_load_reg
+                };
+
+                // evaluate loop condition (index < length) and jump
+                let cmp_instr = InstructionKind::BinaryOp {
+                    op: BinaryOperator::Less,
+                    l: self.block_context.get_variable(&index_ident),
+                    r: length_load_reg
+                };
+                let cmp_result = self.function_context.new_register(Type::Bool);
+                self.builder.push_instruction(cmp_instr.with_result(cmp_result.clone()));
+
+                let loop_cond_jump = InstructionKind::JumpCond {
+                    cond: cmp_result,
+                    true_label: body_label.clone(),
+                    false_label: end_label,
+                };
+                self.builder.push_instruction(loop_cond_jump.without_result());
+                self.add_loop_exit();
+
+                // compile loop body, that jumps to the step block at the end
+                self.next_block(body_label);
+
+                // get pointer to the current item from an array or list
+                let index_ent = self.block_context.get_variable(&index_ident);
+                let gep_reg = if is_list {
+                    self.compile_list_slot("__builtin_method__list__at__", array_ent, vec![index_ent])
+                } else {
+                    self.compile_array_gep(array_item_t.clone(), array_ent, index_ent)
+                };
 
                 // load current item from the pointer and update environment for the loop body
                 let load_reg = self.function_context.new_register(array_item_t.clone());
                 let load_instr = InstructionKind::Load { ptr: gep_reg };
                 self.builder.push_instruction(load_instr.with_result(load_reg.clone()));
                 // TODO: Cast will be necessary here when inheritance is implemented
-                self.block_context.set_new_variable(ident.clone(), load_reg);
+                // item variable is declared in a nested block, as it may shadow a variable of the enclosing one
+                // (it is also not visible at the step block, so that no excessive phi nodes are generated)
+                let mut compiler = self.nested_for_block();
+                compiler.block_context.set_new_variable(ident, load_reg);
 
                 // compile block body
-                self.compile_statement(*stmt);
+                compiler.compile_statement(*stmt);
+                if !compiler.builder.block_is_terminated() {
+                    compiler.compile_loop_jump(&None, true);
+                }
+                self.merge_block_compiler(compiler);
 
-                // increment index
-                let increment_instr = InstructionKind::BinaryOp {
-                    op: BinaryOperator::Plus,
-                    l: self.block_context.get_variable(&index_ident),
-                    r: Entity::Int { v: 1, uuid: 0 }
-                };
-                let increment_ent = self.function_context.new_register(Type::Int);
-                self.builder.push_instruction(increment_instr.with_result(increment_ent.clone()));
-                self.block_context.update_variable(index_ident.clone(), increment_ent);
+                // step block is reachable only if the body doesn't always exit the loop
+                let mut back_edges = Vec::new();
+                if self.begin_continue_block(step_label) {
+                    // increment index
+                    let increment_instr = InstructionKind::BinaryOp {
+                        op: BinaryOperator::Plus,
+                        l: self.block_context.get_variable(&index_ident),
+                        r: Entity::Int { v: 1, uuid: 0 }
+                    };
+                    let increment_ent = self.function_context.new_register(Type::Int);
+                    self.builder.push_instruction(increment_instr.with_result(increment_ent.clone()));
+                    self.block_context.update_variable(index_ident.clone(), increment_ent);
+
+                    // jump to loop cond evaluation block
+                    let cond_jump = InstructionKind::Jump { label: cond_label };
+                    self.builder.push_instruction(cond_jump.without_result());
+                    back_edges.push((self.builder.get_block_label(), self.block_context.get_env_view()));
+                }
 
-                // jump to loop cond evaluation block
-                self.builder.push_instruction(cond_jump);
+                // start new block with loop end label, index is no longer necessary
+                self.complete_loop(back_edges);
+                self.block_context.remove_variable(&index_ident);
+            }
+            StatementKind::ForLoop { label, init, cond, step, stmt } => {
+                // variables declared in the initializer are visible only inside the loop
+                let mut compiler = self.nested_for_block();
+                compiler.compile_for_loop(label, init, cond, step, *stmt);
+                self.merge_block_compiler(compiler);
+            }
+            StatementKind::DoWhile { label, stmt, expr } => {
+                let suffix = self.global_context.new_label_suffix();
+                let body_label = format!("__do_body__{}", suffix);
+                let cond_label = format!("__do_cond__{}", suffix);
+                let end_label = format!("__do_end__{}", suffix);
 
-                // remove item variable so that no excessive phi nodes are generated
-                self.block_context.remove_variable(&ident);
+                // body is executed before the condition, so it is the loop header
+                self.begin_loop(label, body_label.clone(), end_label.clone(), cond_label.clone());
+                self.compile_statement(*stmt);
+                if !self.builder.block_is_terminated() {
+                    self.compile_loop_jump(&None, true);
+                }
 
-                // collect information for phi calculation
-                let body_env = self.block_context.get_env_view();
-
-                // begin next block by evaluating necessary phi statements based on block variables
-                self.next_block(cond_label);
-                self.builder.add_predecessor(in_label, &in_env);
-                self.builder.add_predecessor(body_label.clone(), &body_env);
-
-                // get array length
-                let length_gep_instr = InstructionKind::GetStructElementPtr {
-                    container_type_name: self.global_context.get_or_declare_array_struct(&array_item_t).llvm_name(),
-                    var: array_ent,
-                    idx: Entity::Int { v: 0, uuid: 0 }
+                // condition is reachable only if the body doesn't always exit the loop
+                let mut back_edges = Vec::new();
+                if self.begin_continue_block(cond_label) {
+                    let expr_result = self.compile_expression(*expr);
+                    let loop_cond_jump = InstructionKind::JumpCond {
+                        cond: expr_result,
+                        true_label: body_label,
+                        false_label: end_label,
+                    };
+                    self.builder.push_instruction(loop_cond_jump.without_result());
+                    self.add_loop_exit();
+                    back_edges.push((self.builder.get_block_label(), self.block_context.get_env_view()));
+                }
+                self.complete_loop(back_edges);
+            }
+            StatementKind::Switch { expr, cases, default } => {
+                // create labels for all branches
+                let suffix = self.global_context.new_label_suffix();
+                let default_label = format!("__switch__default__{}", suffix);
+                let end_label = format!("__switch__end__{}", suffix);
+                let case_labels: Vec<String> = (0..cases.len())
+                    .map(|idx| format!("__switch__case__{}__{}", suffix, idx))
+                    .collect();
+                let no_match_label = if default.is_some() {
+                    default_label.clone()
+                } else {
+                    end_label.clone()
                 };
-                let length_gep_reg = self.function_context.new_register(Type::Int.reference());
-                self.builder.push_instruction(length_gep_instr.with_result(length_gep_reg.clone()));
 
-                let length_load_reg = self.function_context.new_register(Type::Int);
-                let length_load_instr = InstructionKind::Load { ptr: length_gep_reg };
-                self.builder.push_instruction(length_load_instr.with_result(length_load_reg.clone()));
+                // evaluate the expression only once, and pair every case value with its branch label
+                let switch_t = expr.get_type();
+                let switch_ent = self.compile_expression(*expr);
+                let case_targets: Vec<(Expression<TypeMeta>, String)> = cases.iter()
+                    .zip(case_labels.iter())
+                    .flat_map(|(case, label)| {
+                        case.item.vals.iter().map(move |val| (*val.clone(), label.clone()))
+                    })
+                    .collect();
 
-                // evaluate loop condition (index < length) and jump
-                let cmp_instr = InstructionKind::BinaryOp {
-                    op: BinaryOperator::Less,
-                    l: self.block_context.get_variable(&index_ident),
-                    r: length_load_reg
-                };
-                let cmp_result = self.function_context.new_register(Type::Bool);
-                self.builder.push_instruction(cmp_instr.with_result(cmp_result.clone()));
+                // collect information for phi calculation
+                let in_env = self.block_context.get_env_view();
+                let mut predecessors = Vec::new();
+
+                if switch_t != Type::Str && Self::is_dense_switch(&case_targets) {
+                    // dense integer switch is compiled to a single instruction
+                    let switch_cases = case_targets.into_iter()
+                        .map(|(val, label)| (self.compile_expression(val), label))
+                        .collect();
+                    let switch_instr = InstructionKind::Switch {
+                        val: switch_ent,
+                        default_label: no_match_label,
+                        cases: switch_cases,
+                    };
+                    self.builder.push_instruction(switch_instr.without_result());
+                } else {
+                    // otherwise, we compare the value with each of the cases in a separate block
+                    for (idx, (val, label)) in case_targets.into_iter().enumerate() {
+                        let next_label = format!("__switch__test__{}__{}", suffix, idx);
+                        let cmp_ent = self.compile_switch_comparison(switch_ent.clone(), val);
+                        let cond_jump = InstructionKind::JumpCond {
+                            cond: cmp_ent,
+                            true_label: label,
+                            false_label: next_label.clone(),
+                        };
+                        self.builder.push_instruction(cond_jump.without_result());
+                        self.next_block(next_label);
+                    }
+                    let no_match_jump = InstructionKind::Jump { label: no_match_label };
+                    self.builder.push_instruction(no_match_jump.without_result());
+                }
+                if default.is_none() {
+                    predecessors.push((self.builder.get_block_label(), in_env.clone()));
+                }
 
-                let loop_cond_jump = InstructionKind::JumpCond {
-                    cond: cmp_result,
-                    true_label: body_label,
-                    false_label: end_label.clone(),
-                };
-                self.builder.push_instruction(loop_cond_jump.without_result());
+                // compile all branches, each of them jumps to the end unless it returns
+                let mut branches: Vec<_> = cases.into_iter()
+                    .zip(case_labels)
+                    .map(|(case, label)| (case.item.block, label))
+                    .collect();
+                if let Some(block) = default {
+                    branches.push((block, default_label));
+                }
+                // break statement inside a switch jumps to its end, just like at the end of each branch
+                self.loops.push(LoopContext {
+                    label: None,
+                    depth: self.block_context.get_depth(),
+                    header_label: String::new(),
+                    header_phis: Vec::new(),
+                    break_label: end_label.clone(),
+                    continue_label: None,
+                    break_predecessors: Vec::new(),
+                    continue_predecessors: Vec::new(),
+                });
+                for (block, label) in branches {
+                    self.block_context.restore_env(&in_env);
+                    self.next_block(label);
+                    let meta = block.get_meta().clone();
+                    self.compile_statement(Statement::new(StatementKind::Block { block }, meta));
+                    if !self.builder.block_is_terminated() {
+                        self.compile_loop_jump(&None, false);
+                    }
+                }
+                predecessors.append(&mut self.loops.pop().unwrap().break_predecessors);
 
-                // start new block with loop end label, and fix mapping in 2 previous blocks
-                self.complete_loop_block(end_label);
+                // if any of the branches (or no match) leads to the end block, create it
+                if !predecessors.is_empty() {
+                    self.join_blocks(end_label, predecessors);
+                }
+            }
+            StatementKind::Break { label } => {
+                self.compile_loop_jump(&label, false);
+            }
+            StatementKind::Continue { label } => {
+                self.compile_loop_jump(&label, true);
             }
             StatementKind::Expr { expr } => {
                 self.compile_expression(*expr);
             }
+            StatementKind::Throw { expr } => {
+                let exception_ent = self.compile_expression(*expr);
+                let cast_instr = InstructionKind::BitCast { ent: exception_ent, to: Type::Str };
+                let cast_ent = self.function_context.new_register(Type::Str);
+                self.builder.push_instruction(cast_instr.with_result(cast_ent.clone()));
+                self.compile_throw(cast_ent);
+            }
+            StatementKind::Assert { expr, message, location } => {
+                let suffix = self.global_context.new_label_suffix();
+                let fail_label = format!("__assert__fail__{}", suffix);
+                let end_label = format!("__assert__end__{}", suffix);
+
+                let cond_result = self.compile_expression(*expr);
+                let cond_jump_instr = InstructionKind::JumpCond {
+                    cond: cond_result,
+                    true_label: end_label.clone(),
+                    false_label: fail_label.clone(),
+                };
+                self.builder.push_instruction(cond_jump_instr.without_result());
+                let mut predecessors = vec![(self.builder.get_block_label(), self.block_context.get_env_view())];
+
+                // failure message (evaluated only here) is printed by the runtime, that also exits the program
+                self.next_block(fail_label);
+                let location_ent = self.compile_string_constant(&location);
+                let message_ent = match message {
+                    Some(message) => self.compile_expression(*message),
+                    None => Entity::Null { uuid: 0, t: Type::Str },
+                };
+                let fail_instr = InstructionKind::Call {
+                    func: String::from("__builtin_method__assert__fail__"),
+                    args: vec![location_ent, message_ent],
+                };
+                self.builder.push_instruction(fail_instr.without_result());
+                let jump_instr = InstructionKind::Jump { label: end_label.clone() };
+                self.builder.push_instruction(jump_instr.without_result());
+                predecessors.push((self.builder.get_block_label(), self.block_context.get_env_view()));
+
+                self.join_blocks(end_label, predecessors);
+            }
+            StatementKind::Try { block, catches, finally } => {
+                // create labels for all branches
+                let suffix = self.global_context.new_label_suffix();
+                let catch_label = format!("__try__catch__{}", suffix);
+                let rethrow_label = format!("__try__rethrow__{}", suffix);
+                let end_label = format!("__try__end__{}", suffix);
+                let end_jump_instr = InstructionKind::Jump { label: end_label.clone() }
+                    .without_result();
+                let mut predecessors = Vec::new();
+
+                // exceptions thrown inside the protected block jump to the catch clauses
+                self.begin_try(catch_label.clone(), finally.clone());
+                let meta = block.get_meta().clone();
+                self.compile_statement(Statement::new(StatementKind::Block { block }, meta));
+                if !self.builder.block_is_terminated() {
+                    self.builder.push_instruction(end_jump_instr.clone());
+                    predecessors.push((self.builder.get_block_label(), self.block_context.get_env_view()));
+                }
+                let handler_predecessors = self.tries.pop().unwrap().handler_predecessors;
+
+                if !handler_predecessors.is_empty() {
+                    // exceptions thrown inside the catch clauses still need to execute the finally block
+                    if finally.is_some() {
+                        self.begin_try(rethrow_label.clone(), finally.clone());
+                    }
+
+                    // exception is matched against catch clauses in order of their declaration
+                    self.join_blocks(catch_label, handler_predecessors);
+                    let exception_ent = self.compile_pending_exception();
+                    let in_env = self.block_context.get_env_view();
+                    for (idx, catch) in catches.into_iter().enumerate() {
+                        let match_label = format!("__try__match__{}__{}", suffix, idx);
+                        let next_label = format!("__try__test__{}__{}", suffix, idx);
+                        let match_ent = self.compile_exception_match(exception_ent.clone(), &catch.item.t);
+                        let cond_jump = InstructionKind::JumpCond {
+                            cond: match_ent,
+                            true_label: match_label.clone(),
+                            false_label: next_label.clone(),
+                        };
+                        self.builder.push_instruction(cond_jump.without_result());
+
+                        // caught exception is no longer propagated, it is visible only inside the catch block
+                        self.block_context.restore_env(&in_env);
+                        self.next_block(match_label);
+                        let clear_instr = InstructionKind::Call {
+                            func: String::from("__builtin_method__exception__clear__"),
+                            args: vec![],
+                        };
+                        self.builder.push_instruction(clear_instr.without_result());
+                        let mut compiler = self.nested_for_block();
+                        let cast_instr = InstructionKind::BitCast {
+                            ent: exception_ent.clone(),
+                            to: catch.item.t.clone(),
+                        };
+                        let cast_ent = compiler.function_context.new_register(catch.item.t.clone());
+                        compiler.builder.push_instruction(cast_instr.with_result(cast_ent.clone()));
+                        compiler.block_context.set_new_variable(catch.item.ident.clone(), cast_ent);
+                        let meta = catch.item.block.get_meta().clone();
+                        compiler.compile_statement(Statement::new(StatementKind::Block { block: catch.item.block }, meta));
+                        self.merge_block_compiler(compiler);
+                        if !self.builder.block_is_terminated() {
+                            self.builder.push_instruction(end_jump_instr.clone());
+                            predecessors.push((self.builder.get_block_label(), self.block_context.get_env_view()));
+                        }
+
+                        self.block_context.restore_env(&in_env);
+                        self.next_block(next_label);
+                    }
+
+                    // exception that doesn't match any of the catch clauses is propagated further
+                    self.compile_exception_jump();
+
+                    // propagated exception is thrown again after executing the finally block
+                    if let Some(finally) = finally.clone() {
+                        let rethrow_predecessors = self.tries.pop().unwrap().handler_predecessors;
+                        if !rethrow_predecessors.is_empty() {
+                            self.join_blocks(rethrow_label, rethrow_predecessors);
+                            let exception_ent = self.compile_pending_exception();
+                            let clear_instr = InstructionKind::Call {
+                                func: String::from("__builtin_method__exception__clear__"),
+                                args: vec![],
+                            };
+                            self.builder.push_instruction(clear_instr.without_result());
+                            let meta = finally.get_meta().clone();
+                            self.compile_statement(Statement::new(StatementKind::Block { block: finally }, meta));
+                            if !self.builder.block_is_terminated() {
+                                self.compile_throw(exception_ent);
+                            }
+                        }
+                    }
+                }
+
+                // finally block is executed after the protected block or any of the catch clauses completes
+                if !predecessors.is_empty() {
+                    self.join_blocks(end_label, predecessors);
+                    if let Some(finally) = finally {
+                        let meta = finally.get_meta().clone();
+                        self.compile_statement(Statement::new(StatementKind::Block { block: finally }, meta));
+                    }
+                }
+            }
             StatementKind::Error => {
                 unreachable!()
             }
         }
     }
 
+    /// sets values of all global variables, in the order of their declaration
+    fn compile_global_initializers(&mut self) {
+        for global in self.global_context.get_global_initializers() {
+            let ident = global.item.get_key().clone();
+            let entity = match global.item.decl {
+                DeclItemKind::NoInit { .. } => self.compile_default_init(&global.item.t),
+                DeclItemKind::Init { val, .. } => {
+                    let original_ent = self.compile_expression(*val);
+                    self.cast_to_expected_type(original_ent, global.item.t.clone())
+                }
+            };
+            self.set_variable(&ident, entity);
+        }
+    }
+
     pub fn compile_function(&mut self, function: Function<TypeMeta>) -> FunctionDef {
         // collect function argument info
         let mut args = Vec::new();
@@ -1214,7 +2600,8 @@ impl FunctionCompiler {
             let arg_type = arg.get_type().clone();
             let mapped_arg_item = ArgItem {
                 t: arg_type.clone(),
-                ident: arg.item.ident.clone()
+                ident: arg.item.ident.clone(),
+                is_final: arg.item.is_final,
             };
             args.push(mapped_arg_item);
 
@@ -1227,12 +2614,20 @@ impl FunctionCompiler {
         }
 
         // compile function instructions using the nested compiler
+        self.ret_type = function.item.ret.clone();
+        self.is_main = function.item.ident == "main";
+        if self.is_main {
+            self.compile_global_initializers();
+        }
         for stmt in function.item.block.item.stmts {
+            if self.builder.block_is_terminated() {
+                break;
+            }
             self.compile_statement(*stmt);
         }
 
         // finish last block within the nested compiler
-        let block = self.builder.build(&mut self.function_context);
+        let block = self.builder.build();
         self.function_context.push_block(block);
 
         let ret_type = function.item.ret.clone();
@@ -1279,7 +2674,8 @@ impl FunctionCompiler {
             let mapped_arg_item = ArgItem {
                 t: arg_type.as_ref().clone(),
 //                ident: arg_ident.item.ident.clone()
-                ident: arg_ident.clone()
+                ident: arg_ident.clone(),
+                is_final: arg_reg > 0 && function.item.args[arg_reg - 1].item.is_final,
             };
             args.push(mapped_arg_item);
 
@@ -1292,12 +2688,16 @@ impl FunctionCompiler {
         }
 
         // compile function instructions using the nested compiler
+        self.ret_type = function.item.ret.clone();
         for stmt in function.item.block.item.stmts {
+            if self.builder.block_is_terminated() {
+                break;
+            }
             self.compile_statement(*stmt);
         }
 
         // finish last block within the nested compiler
-        let block = self.builder.build(&mut self.function_context);
+        let block = self.builder.build();
         self.function_context.push_block(block);
 
         let ret_type = function.item.ret.clone();
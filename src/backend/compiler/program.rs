@@ -1,5 +1,5 @@
 use crate::backend::context::GlobalContext;
-use crate::frontend::ast::{Program, Class, ClassItem};
+use crate::frontend::ast::{Program, Class, Type, Block, Statement, StatementKind};
 use crate::meta::{TypeMeta, GetType};
 use crate::backend::ir::LLVM;
 use crate::backend::compiler::function::FunctionCompiler;
@@ -24,6 +24,8 @@ impl ProgramCompiler {
     pub fn with_builtin_functions(declarations: &mut Vec<String>) -> Self {
         let mut compiler = Self::new();
         compiler.global_context.append_function_declarations(declarations);
+        // string array struct is always declared, as it is returned by the split builtin
+        compiler.global_context.get_or_declare_array_struct(&Type::Str);
         compiler
     }
 
@@ -53,6 +55,10 @@ impl ProgramCompiler {
         while classes.len() > processed_classes.len() {
             // this loop is really inefficient, but it's not a problem unless we get > 10k total classes
             for (class_name, class) in classes {
+                if processed_classes.contains(class_name) {
+                    // every class is declared once, so that it gets a single class id
+                    continue;
+                }
                 if let Some(parent) = &class.item.parent {
                     // we apply inheritance only when parent already has all its inherited properties
                     if let Some(parent_cls) = processed_classes.get(parent) {
@@ -84,12 +90,63 @@ impl ProgramCompiler {
         }
     }
 
+    /// checks if the block contains a throw statement (at any depth)
+    fn block_throws(block: &Block<TypeMeta>) -> bool {
+        block.item.stmts.iter().any(|stmt| Self::statement_throws(stmt))
+    }
+
+    fn statement_throws(stmt: &Statement<TypeMeta>) -> bool {
+        match &stmt.item {
+            StatementKind::Throw { .. } => true,
+            StatementKind::Block { block } => Self::block_throws(block),
+            StatementKind::Cond { stmt, .. }
+            | StatementKind::While { stmt, .. }
+            | StatementKind::For { stmt, .. }
+            | StatementKind::ForLoop { stmt, .. }
+            | StatementKind::DoWhile { stmt, .. } => Self::statement_throws(stmt),
+            StatementKind::CondElse { stmt_true, stmt_false, .. } => {
+                Self::statement_throws(stmt_true) || Self::statement_throws(stmt_false)
+            }
+            StatementKind::Switch { cases, default, .. } => {
+                cases.iter().any(|case| Self::block_throws(&case.item.block))
+                    || default.as_ref().is_some_and(Self::block_throws)
+            }
+            StatementKind::Try { block, catches, finally } => {
+                Self::block_throws(block)
+                    || catches.iter().any(|catch| Self::block_throws(&catch.item.block))
+                    || finally.as_ref().is_some_and(Self::block_throws)
+            }
+            _ => false,
+        }
+    }
+
     pub fn compile_program(&mut self, program: Program<TypeMeta>) -> Vec<LLVM> {
         // declare structures for all classes
         self.declare_classes(&program.classes);
 
+        // calls have to check for exceptions only if any function or method throws them
+        let throws = program.functions.values().any(|func| Self::block_throws(&func.item.block))
+            || program.classes.values()
+                .flat_map(|cls| cls.item.methods.values())
+                .any(|method| Self::block_throws(&method.item.block));
+        // extern functions are only declared, they never throw exceptions
+        let (extern_functions, functions): (Vec<_>, Vec<_>) = program.functions.values()
+            .partition(|func| func.item.is_extern);
+        for func in extern_functions {
+            self.global_context.declare_extern_function(func);
+        }
+        if throws {
+            let checked = functions.iter().map(|func| func.item.ident.clone()).collect();
+            self.global_context.enable_exception_checks(checked);
+        }
+
+        // global variables are declared before compiling any function that could use them
+        for global in program.globals.iter() {
+            self.global_context.declare_global_variable(global);
+        }
+
         // compile all functions
-        let mut compiled_functions: Vec<LLVM> = program.functions.values()
+        let mut compiled_functions: Vec<LLVM> = functions.into_iter()
             .map(|func| {
                 let mut function_compiler = FunctionCompiler::new(&self.global_context);
                 let compiled_function = function_compiler.compile_function(func.clone());
@@ -38,6 +38,11 @@ impl BlockContext {
         panic!("Identifier not found: {}", ident)
     }
 
+    /// checks if a variable with given identifier is visible at the current depth
+    pub fn has_variable(&self, ident: &String) -> bool {
+        self.env_stack.iter().any(|env| env.contains_key(ident))
+    }
+
     /// update existing variable value from the current depth
     /// (the related entity with closest depth to current one will be updated)
     pub fn update_variable(&mut self, ident: String, ent: Entity) {
@@ -65,11 +70,48 @@ impl BlockContext {
     pub fn get_env_view(&self) -> Env<Entity> {
         let mut combined_env = Env::new();
         for env in self.env_stack.iter() {
-            combined_env.extend(env.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+            combined_env.extend(env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        combined_env
+    }
+
+    /// get current depth, necessary to later get view of the environment at that depth
+    pub fn get_depth(&self) -> usize {
+        self.current_depth
+    }
+
+    /// get view of the environment from the given depth (variables from deeper levels are ignored)
+    pub fn get_env_view_at_depth(&self, depth: usize) -> Env<Entity> {
+        let mut combined_env = Env::new();
+        for env in self.env_stack.iter().take(depth + 1) {
+            combined_env.extend(env.iter().map(|(k, v)| (k.clone(), v.clone())));
         }
         combined_env
     }
 
+    /// leave all levels deeper than the given depth, so that variables declared there are not visible,
+    /// returns their environments to allow restoring them later
+    pub fn leave_to_depth(&mut self, depth: usize) -> Vec<Env<Entity>> {
+        self.current_depth = depth;
+        self.env_stack.split_off(depth + 1)
+    }
+
+    /// restore levels previously removed with leave_to_depth
+    pub fn restore_levels(&mut self, levels: Vec<Env<Entity>>) {
+        self.current_depth += levels.len();
+        self.env_stack.extend(levels);
+    }
+
+    /// set all variables to the values from previously saved environment view
+    /// (variables that no longer exist at current depth are ignored)
+    pub fn restore_env(&mut self, env_view: &Env<Entity>) {
+        for (ident, ent) in env_view.iter() {
+            if let Some(env) = self.env_stack.iter_mut().rev().find(|env| env.contains_key(ident)) {
+                env.insert(ident.clone(), ent.clone());
+            }
+        }
+    }
+
     /// substitute entities in entire environment stack using provided mapping
     pub fn map_env(&mut self, mapping: &HashMap<Entity, Entity>) {
         let increment_mapper = IncrementMapper { offset: 0, from_value: 0 };
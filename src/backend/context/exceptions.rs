@@ -0,0 +1,24 @@
+use crate::backend::context::Predecessor;
+use crate::frontend::ast::Block;
+use crate::meta::TypeMeta;
+
+/// information about the try statement that is currently compiled,
+/// necessary to compile statements that throw or leave the try statement in other way
+#[derive(Debug, Clone)]
+pub struct TryContext {
+    /// depth of the block context outside of the try statement
+    pub depth: usize,
+
+    /// label of the block where exceptions thrown inside the try statement are handled
+    pub handler_label: String,
+
+    /// blocks that jump to the handler label
+    pub handler_predecessors: Vec<Predecessor>,
+
+    /// block that has to be executed when control leaves the try statement
+    pub finally: Option<Block<TypeMeta>>,
+
+    /// number of loops (and switches) enclosing the try statement,
+    /// break and continue statements exiting any of them also leave the try statement
+    pub loop_count: usize,
+}
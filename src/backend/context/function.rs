@@ -49,12 +49,25 @@ impl FunctionContext {
         self.compiled_blocks.push(block);
     }
 
-    /// map entities in instructions from the last compiled block using provided mapping
-    pub fn map_entities_in_last_block(&mut self, mapping: &HashMap<Entity, Entity>) {
-        let mut last_block = self.compiled_blocks.pop().unwrap();
+    /// map entities in instructions from all compiled blocks using provided mapping
+    pub fn map_entities(&mut self, mapping: &HashMap<Entity, Entity>) {
         let increment_mapper = IncrementMapper { offset: 0, from_value: 0 };
-        last_block = last_block.map_entities(&increment_mapper, mapping);
-        self.compiled_blocks.push(last_block);
+        self.compiled_blocks = self.compiled_blocks.iter()
+            .map(|block| block.map_entities(&increment_mapper, mapping))
+            .collect();
+    }
+
+    /// replace phi instructions at the beginning of already compiled block with the given ones
+    pub fn replace_phi_instructions(&mut self, label: &String, phi_instructions: Vec<Instruction>) {
+        let block = self.compiled_blocks.iter_mut()
+            .find(|block| block.get_label() == *label)
+            .unwrap();
+        let other_instructions: Vec<Instruction> = block.instructions.drain(..)
+            .skip_while(|instr| matches!(instr.item, InstructionKind::Phi { .. }))
+            .collect();
+        block.instructions = phi_instructions.into_iter()
+            .chain(other_instructions)
+            .collect();
     }
 
     /// concludes the current block and returns all compiled blocks
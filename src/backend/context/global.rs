@@ -1,6 +1,8 @@
-use crate::backend::ir::{LLVM, StringDecl, StructDecl, VTableDecl};
+use itertools::Itertools;
+
+use crate::backend::ir::{GlobalDecl, LLVM, StringDecl, StructDecl, VTableDecl};
 use crate::util::env::Env;
-use crate::frontend::ast::{Class, Keyed, ClassVar, Type};
+use crate::frontend::ast::{Class, Keyed, Expression, Function, Global, Type, unescape_string_literal};
 use crate::meta::{TypeMeta, GetType};
 use std::collections::{HashMap, HashSet};
 
@@ -26,6 +28,27 @@ pub struct GlobalContext {
 
     /// unique suffix that can be used for new label (or set of labels)
     available_label_suffix: usize,
+
+    /// class name to parent class name mapping, necessary to match caught exceptions
+    class_parents: Env<Option<String>>,
+
+    /// program functions, calls to them are followed by exception check if the program throws exceptions
+    checked_functions: HashSet<String>,
+
+    /// true if the program throws exceptions, so that method calls need to be checked as well
+    exceptions_enabled: bool,
+
+    /// extern functions are called using their original (not mangled) names
+    extern_functions: HashSet<String>,
+
+    /// global variable name to global variable declaration mapping
+    global_declarations: Env<GlobalDecl>,
+
+    /// global variables in the order of initialization, done at the beginning of main
+    global_initializers: Vec<Global<TypeMeta>>,
+
+    /// class name to initial values of its fields (including inherited ones), set when an object is created
+    field_initializers: Env<Vec<(String, Expression<TypeMeta>)>>,
 }
 
 impl GlobalContext {
@@ -38,6 +61,13 @@ impl GlobalContext {
             array_struct_definitions: HashMap::new(),
             available_const: 1,
             available_label_suffix: 1,
+            class_parents: Env::new(),
+            checked_functions: HashSet::new(),
+            exceptions_enabled: false,
+            extern_functions: HashSet::new(),
+            global_declarations: Env::new(),
+            global_initializers: Vec::new(),
+            field_initializers: Env::new(),
         }
     }
 
@@ -47,10 +77,11 @@ impl GlobalContext {
             existing_decl.clone()
         } else {
             let const_name = self.new_const_name();
+            let bytes = unescape_string_literal(&val);
             let new_decl = StringDecl {
                 name: const_name.clone(),
-                val: val.clone(),
-                len: val.len() - 1,  // -2 for quotes +1 for trailing zero
+                len: bytes.len() + 1,  // +1 for trailing zero
+                bytes,
             };
             self.string_declarations.insert(val, new_decl.clone());
             new_decl
@@ -62,6 +93,44 @@ impl GlobalContext {
         self.function_declarations.append(declarations);
     }
 
+    /// declare function implemented outside of the program, it will be resolved by the linker
+    pub fn declare_extern_function(&mut self, function: &Function<TypeMeta>) {
+        let args = function.item.args.iter()
+            .map(|arg| &arg.item.t)
+            .join(", ");
+        self.function_declarations.push(
+            format!("declare {} @{}({})", function.item.ret, function.item.ident, args)
+        );
+        self.extern_functions.insert(function.item.ident.clone());
+    }
+
+    /// declare global variable, its value is set before executing main
+    pub fn declare_global_variable(&mut self, global: &Global<TypeMeta>) {
+        let ident = global.item.get_key();
+        if let Type::Array { item_t } = &global.item.t {
+            // the variable refers to the array struct type even if the array is never used
+            self.get_or_declare_array_struct(item_t);
+        }
+        let decl = GlobalDecl { name: format!("__global__{}", ident), t: global.item.t.clone() };
+        self.global_declarations.insert(ident.clone(), decl);
+        self.global_initializers.push(global.clone());
+    }
+
+    /// get declaration of the global variable by its source code identifier
+    pub fn get_global_variable(&self, ident: &String) -> GlobalDecl {
+        self.global_declarations.get(ident).unwrap().clone()
+    }
+
+    /// get all global variables in the order of initialization
+    pub fn get_global_initializers(&self) -> Vec<Global<TypeMeta>> {
+        self.global_initializers.clone()
+    }
+
+    /// get initial values of the fields of the class, including the inherited ones
+    pub fn get_field_initializers(&self, class_name: &String) -> Vec<(String, Expression<TypeMeta>)> {
+        self.field_initializers.get(class_name).cloned().unwrap_or_default()
+    }
+
     fn get_parent_struct_decl(&self, cls: &Class<TypeMeta>) -> Option<StructDecl> {
         if let Some(parent_cls) = &cls.item.parent {
             self.struct_declarations.get(parent_cls).cloned()
@@ -83,6 +152,17 @@ impl GlobalContext {
     /// (function definitions for class methods are compiled separately)
     pub fn declare_class(&mut self, cls: &Class<TypeMeta>) -> StructDecl {
         let class_name = cls.item.get_key();
+        self.class_parents.insert(class_name.clone(), cls.item.parent.clone());
+
+        // fields with initial values are set after the object is allocated, parent class is declared earlier
+        let mut field_initializers = cls.item.parent.as_ref()
+            .map_or(Vec::new(), |parent| self.get_field_initializers(parent));
+        for (field_name, field_var) in cls.item.vars.iter() {
+            if let Some(val) = &field_var.item.val {
+                field_initializers.push((field_name.clone(), *val.clone()));
+            }
+        }
+        self.field_initializers.insert(class_name.clone(), field_initializers);
 
         // create a vtable for current class, unifying it with parent class to preserve order
         let mut method_types = Vec::new();
@@ -131,6 +211,7 @@ impl GlobalContext {
         let vtable_decl = VTableDecl {
             name: self.vtable_struct_name(class_name),
             data_const_name: self.vtable_struct_const(class_name),
+            class_id: self.struct_vtable_declarations.len() as i32,
             methods: method_declarations,
             method_env
         };
@@ -173,6 +254,41 @@ impl GlobalContext {
         new_struct
     }
 
+    /// get names of the class and all its (direct or indirect) subclasses, in alphabetical order
+    pub fn get_subclasses(&self, class_name: &String) -> Vec<String> {
+        let mut subclasses: Vec<String> = self.class_parents.keys()
+            .filter(|cls| {
+                let mut current = Some(*cls);
+                while let Some(cls) = current {
+                    if cls == class_name {
+                        return true;
+                    }
+                    current = self.class_parents.get(cls).and_then(Option::as_ref);
+                }
+                false
+            })
+            .cloned()
+            .collect();
+        subclasses.sort();
+        subclasses
+    }
+
+    /// enables exception checks after calls to the given program functions and all methods
+    pub fn enable_exception_checks(&mut self, function_idents: HashSet<String>) {
+        self.checked_functions = function_idents;
+        self.exceptions_enabled = true;
+    }
+
+    /// checks if the call to a function with given identifier has to be followed by exception check
+    pub fn is_function_checked(&self, func_name: &String) -> bool {
+        self.exceptions_enabled && self.checked_functions.contains(func_name)
+    }
+
+    /// checks if method calls have to be followed by exception check
+    pub fn are_methods_checked(&self) -> bool {
+        self.exceptions_enabled
+    }
+
     pub fn struct_name(&self, class_name: &String) -> String {
         format!("__class__{}", class_name)
     }
@@ -215,11 +331,11 @@ impl GlobalContext {
     }
 
     pub fn get_array_struct_name(&self, item_t: &Type) -> String {
-        format!("__builtin_struct__array_{}", item_t).replace("*", "ptr")
+        format!("__builtin_struct__array_{}", item_t).replace("*", "ptr").replace("%", "")
     }
 
     pub fn get_array_struct_size_name(&self, item_t: &Type) -> String {
-        format!("__builtin_sizeof__array_{}", item_t).replace("*", "ptr")
+        format!("__builtin_sizeof__array_{}", item_t).replace("*", "ptr").replace("%", "")
     }
 
     /// get new unique name for a global constant
@@ -243,14 +359,19 @@ impl GlobalContext {
 
     /// get mangled function name from its source code identifier
     pub fn get_function_name(&self, func_name: &String) -> String {
-        if func_name == "main" {
-            // main is the only non-mangled function
+        if func_name == "main" || self.extern_functions.contains(func_name) {
+            // main and extern functions are the only non-mangled functions
             func_name.clone()
         } else {
             format!("__func__{}", func_name)
         }
     }
 
+    /// get name of the function implemented in the runtime, separate from the names of program functions
+    pub fn get_native_function_name(&self, func_name: &String) -> String {
+        format!("__builtin_func__{}", func_name)
+    }
+
     /// get struct declaration from the original class identifier
     pub fn get_struct_decl(&self, class_ident: &String) -> StructDecl {
         self.struct_declarations.get(class_ident).unwrap().clone()
@@ -273,11 +394,14 @@ impl GlobalContext {
             .map(|decl| LLVM::DeclVTable{ decl: decl.clone() });
         let array_struct_decl = self.array_struct_definitions.values()
             .map(|decl| LLVM::DeclStruct{ decl: decl.clone() });
+        let global_decl = self.global_declarations.values()
+            .map(|decl| LLVM::DeclGlobal { decl: decl.clone() });
         llvm_func_decl
             .chain(llvm_str_decl)
             .chain(llvm_struct_decl)
             .chain(vtable_decl)
             .chain(array_struct_decl)
+            .chain(global_decl)
             .collect()
     }
 }
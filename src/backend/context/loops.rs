@@ -0,0 +1,47 @@
+use crate::backend::ir::Entity;
+use crate::util::env::Env;
+
+/// label of the predecessor block and variable environment at its end
+pub type Predecessor = (String, Env<Entity>);
+
+/// information about the loop (or switch) that is currently compiled,
+/// necessary to compile break and continue statements
+#[derive(Debug, Clone)]
+pub struct LoopContext {
+    /// label given to the loop by the user
+    pub label: Option<String>,
+
+    /// depth of the block context outside of the loop
+    pub depth: usize,
+
+    /// label of the block with loop condition, that contains placeholder phi instructions
+    pub header_label: String,
+
+    /// variable identifier => (phi entity, argument from the block before the loop)
+    pub header_phis: Vec<(String, Entity, (Entity, String))>,
+
+    /// label of the block after the loop, where break statements jump
+    pub break_label: String,
+
+    /// label of the block where continue statements jump (none for switch statement)
+    pub continue_label: Option<String>,
+
+    /// blocks that jump to the break label
+    pub break_predecessors: Vec<Predecessor>,
+
+    /// blocks that jump to the continue label
+    pub continue_predecessors: Vec<Predecessor>,
+}
+
+impl LoopContext {
+    /// checks if break or continue statement with given label refers to this loop
+    pub fn matches(&self, label: &Option<String>, is_continue: bool) -> bool {
+        if is_continue && self.continue_label.is_none() {
+            false
+        } else if label.is_some() {
+            self.label == *label
+        } else {
+            true
+        }
+    }
+}
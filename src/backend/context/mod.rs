@@ -1,9 +1,13 @@
 pub use self::block::BlockContext;
+pub use self::exceptions::TryContext;
 pub use self::function::FunctionContext;
 pub use self::global::GlobalContext;
+pub use self::loops::{LoopContext, Predecessor};
 
 mod block;
+mod exceptions;
 mod function;
 mod global;
+mod loops;
 
 
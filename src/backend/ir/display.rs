@@ -1,9 +1,10 @@
 use std::fmt::{Display, Error, Formatter};
+use std::iter;
 use std::string::ToString;
 
 use itertools::{Itertools, join};
 
-use crate::backend::ir::{BasicBlock, Entity, FunctionDef, GetEntity, Instruction, InstructionKind, LLVM, StringDecl, StructDecl, VTableDecl};
+use crate::backend::ir::{BasicBlock, Entity, FunctionDef, GetEntity, GlobalDecl, Instruction, InstructionKind, LLVM, StringDecl, StructDecl, VTableDecl};
 use crate::frontend::ast::{BinaryOperator, Type, UnaryOperator};
 use crate::meta::GetType;
 
@@ -11,8 +12,11 @@ impl Display for Type {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
             Type::Int => write!(f, "i32"),
+            Type::Long => write!(f, "i64"),
             Type::Str => write!(f, "i8*"),
             Type::Bool => write!(f, "i1"),
+            Type::Char => write!(f, "i8"),
+            Type::Double => write!(f, "double"),
             Type::Void => write!(f, "void"),
             Type::Reference { t } => {
                 write!(f, "{}*", t)
@@ -21,9 +25,12 @@ impl Display for Type {
             Type::BuiltinClass { ident } => write!(f, "%{}*", ident),
             Type::Array { item_t } => {
                 let formatted = format!("__builtin_struct__array_{}", item_t)
-                    .replace("*", "ptr");
+                    .replace("*", "ptr").replace("%", "");
                 write!(f, "%{}*", formatted)
             }
+            // list and map layouts are known only to the runtime library
+            Type::List { .. } => write!(f, "%__builtin_struct__list*"),
+            Type::Map { .. } => write!(f, "%__builtin_struct__map*"),
             Type::Function { args, ret } => {
                 let formatted_args = args.iter()
                     .map(|t| format!("{}", t))
@@ -61,10 +68,36 @@ impl Display for BinaryOperator {
             BinaryOperator::NotEqual => write!(f, "icmp ne"),
             BinaryOperator::And => write!(f, "and"),
             BinaryOperator::Or => write!(f, "or"),
+            BinaryOperator::BitAnd => write!(f, "and"),
+            BinaryOperator::BitOr => write!(f, "or"),
+            BinaryOperator::BitXor => write!(f, "xor"),
+            BinaryOperator::ShiftLeft => write!(f, "shl"),
+            BinaryOperator::ShiftRight => write!(f, "ashr"),
+            BinaryOperator::ShiftRightLogical => write!(f, "lshr"),
         }
     }
 }
 
+/// floating point equivalent of the operator, comparisons are ordered (false if any argument is NaN)
+/// except for inequality, which is true for NaN
+fn get_float_operator(op: &BinaryOperator) -> String {
+    let llvm_op = match op {
+        BinaryOperator::Plus => "fadd",
+        BinaryOperator::Minus => "fsub",
+        BinaryOperator::Times => "fmul",
+        BinaryOperator::Divide => "fdiv",
+        BinaryOperator::Modulo => "frem",
+        BinaryOperator::Less => "fcmp olt",
+        BinaryOperator::LessEqual => "fcmp ole",
+        BinaryOperator::Greater => "fcmp ogt",
+        BinaryOperator::GreaterEqual => "fcmp oge",
+        BinaryOperator::Equal => "fcmp oeq",
+        BinaryOperator::NotEqual => "fcmp une",
+        op => panic!("unexpected floating point operator: {:?}", op),
+    };
+    String::from(llvm_op)
+}
+
 impl Display for Entity {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
@@ -72,7 +105,11 @@ impl Display for Entity {
             Entity::Register { n, t: _ } => write!(f, "%{}", n),
             Entity::NamedRegister { name, t: _ } => write!(f, "%{}", name),
             Entity::Int { v, uuid: _ } => write!(f, "{}", v),
+            Entity::Long { v, uuid: _ } => write!(f, "{}", v),
             Entity::Bool { v, uuid: _ } => write!(f, "{}", v),
+            Entity::Char { v, uuid: _ } => write!(f, "{}", *v as i8),
+            // hexadecimal format is the only one that represents every double exactly
+            Entity::Double { v, uuid: _ } => write!(f, "0x{:016X}", v),
             Entity::GlobalConst { name, t } => write!(f, "@{}", name),
         }
     }
@@ -121,15 +158,24 @@ impl Display for Instruction {
             }
             InstructionKind::UnaryOp { op, arg } => {
                 match op {
-                    UnaryOperator::Neg => write!(f, "{} = sub i32 0, {}", self.get_entity(), arg),
+                    UnaryOperator::Neg if arg.get_type() == Type::Double => {
+                        write!(f, "{} = fneg double {}", self.get_entity(), arg)
+                    }
+                    UnaryOperator::Neg => write!(f, "{} = sub {} 0, {}", self.get_entity(), arg.get_type(), arg),
                     UnaryOperator::Not => write!(f, "{} = add i1 {}, 1", self.get_entity(), arg),
+                    UnaryOperator::BitNot => write!(f, "{} = xor {} {}, -1", self.get_entity(), arg.get_type(), arg),
                 }
             }
             InstructionKind::BinaryOp { op, l, r } => {
+                let llvm_op = if l.get_type() == Type::Double {
+                    get_float_operator(op)
+                } else {
+                    op.to_string()
+                };
                 write!(
                     f, "{} = {} {} {}, {}",
                     self.get_entity(),
-                    op, l.get_type(),
+                    llvm_op, l.get_type(),
                     l, r
                 )
             }
@@ -159,6 +205,24 @@ impl Display for Instruction {
                     write!(f, "call void {} ({})", func, args)
                 }
             }
+            InstructionKind::CallVariadic { func, fixed_args, args } => {
+                // variadic function has to be called with its full type
+                let fixed_types = args.iter()
+                    .take(*fixed_args)
+                    .map(|ent| format!("{}, ", ent.get_type()))
+                    .join("");
+                let args = args.iter()
+                    .map(|ent| format!("{} {}", ent.get_type(), ent))
+                    .join(",");
+                if self.has_result_entity() {
+                    write!(
+                        f, "{} = call {} ({}...) @{} ({})",
+                        self.get_entity(), self.get_type(), fixed_types, func, args
+                    )
+                } else {
+                    write!(f, "call void ({}...) @{} ({})", fixed_types, func, args)
+                }
+            }
             InstructionKind::RetVal { val } => {
                 write!(f, "ret {} {}", val.get_type(), val)
             }
@@ -175,6 +239,16 @@ impl Display for Instruction {
             InstructionKind::Jump { label } => {
                 write!(f, "br label %{}", label)
             }
+            InstructionKind::Switch { val, default_label, cases } => {
+                let switch_cases = cases.iter()
+                    .map(|(ent, label)| format!("{} {}, label %{}", ent.get_type(), ent, label))
+                    .join(" ");
+                write!(
+                    f, "switch {} {}, label %{} [ {} ]",
+                    val.get_type(), val,
+                    default_label, switch_cases
+                )
+            }
             InstructionKind::Phi { args } => {
                 let phi_args = args.iter()
                     .map(|(ent, label)| format!("[{}, %{}]", ent, label))
@@ -185,6 +259,15 @@ impl Display for Instruction {
                     self.get_type(), phi_args
                 )
             }
+            InstructionKind::Select { cond, val_true, val_false } => {
+                write!(
+                    f, "{} = select {} {}, {} {}, {} {}",
+                    self.get_entity(),
+                    cond.get_type(), cond,
+                    self.get_type(), val_true,
+                    self.get_type(), val_false
+                )
+            }
             InstructionKind::GetStructElementPtr { container_type_name, var, idx } => {
                 write!(
                     f, "{} = getelementptr {}, {} {}, i32 0, {} {}",
@@ -231,29 +314,39 @@ impl Display for StructDecl {
 
 impl Display for VTableDecl {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        // declare vtable structure type
-        let method_types = self.methods.iter()
-            .map(|(t,n)| format!("{}*", t))
+        // declare vtable structure type, class id is followed by the methods
+        let item_types = iter::once(String::from("i32"))
+            .chain(self.methods.iter().map(|(t, _)| format!("{}*", t)))
             .join(", ");
-        write!(f, "%{} = type {{ {} }}\n", self.name, method_types)?;
+        writeln!(f, "%{} = type {{ {} }}", self.name, item_types)?;
 
         // define constant with actual vtable data
-        let method_pointers = self.methods.iter()
-            .map(|(t, n)| format!("{}* @{}", t, n))
+        let items = iter::once(format!("i32 {}", self.class_id))
+            .chain(self.methods.iter().map(|(t, n)| format!("{}* @{}", t, n)))
             .join(", ");
-        write!(f, "@{} = global %{} {{ {} }}", self.data_const_name, self.name, method_pointers)
+        write!(f, "@{} = global %{} {{ {} }}", self.data_const_name, self.name, items)
+    }
+}
+
+impl Display for GlobalDecl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        // globals are initialized at the beginning of main, until then they hold zero values
+        write!(f, "@{} = global {} zeroinitializer", self.name, self.t)
     }
 }
 
 impl Display for StringDecl {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        // string already contains quotes inside
-        let mut s = self.val.clone();
-        s.insert(s.len() - 1, '\\');
-        s.insert(s.len() - 1, '0');
-        s.insert(s.len() - 1, '0');
+        // quotes, backslashes and non-printable characters have to be escaped with their hex codes
+        let s: String = self.bytes.iter()
+            .map(|b| match b {
+                b'"' | b'\\' => format!("\\{:02X}", b),
+                0x20..=0x7e => (*b as char).to_string(),
+                _ => format!("\\{:02X}", b),
+            })
+            .collect();
         write!(
-            f, "@{} = private unnamed_addr constant [{} x i8] c{}",
+            f, "@{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"",
             self.name, self.len, s
         )
     }
@@ -277,11 +370,12 @@ impl Display for FunctionDef {
 impl Display for LLVM {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
-            LLVM::DeclFunction { decl } => write!(f, "{}\n", decl),
-            LLVM::DeclStruct { decl } => write!(f, "{}\n", decl),
+            LLVM::DeclFunction { decl } => writeln!(f, "{}", decl),
+            LLVM::DeclStruct { decl } => writeln!(f, "{}", decl),
             LLVM::DeclString { decl } => write!(f, "{}", decl),
+            LLVM::DeclGlobal { decl } => writeln!(f, "{}", decl),
             LLVM::Function { def } => write!(f, "{}", def),
-            LLVM::DeclVTable { decl } => {write!(f, "{}\n", decl)},
+            LLVM::DeclVTable { decl } => writeln!(f, "{}", decl),
         }
     }
 }
@@ -18,19 +18,22 @@ use crate::util::env::Env;
 pub enum Entity {
     Null { uuid: usize, t: Type },
     Int { v: i32, uuid: usize },
+    Long { v: i64, uuid: usize },
     Bool { v: bool, uuid: usize },
+    Char { v: u8, uuid: usize },
+    /// bit representation of the double value (f64 cannot be hashed)
+    Double { v: u64, uuid: usize },
     Register { n: usize, t: Type },
     NamedRegister { name: String, t: Type },
     GlobalConst { name: String, t: Type },
 }
 
 impl Entity {
-    /// interprets entity as array, returns type of its item
+    /// interprets entity as array (or list), returns type of its item
     pub fn get_array_item_t(&self) -> Type {
-        if let Type::Array { item_t } = self.get_type() {
-            item_t.as_ref().clone()
-        } else {
-            panic!("expected array type, got {}", self.get_type())
+        match self.get_type() {
+            Type::Array { item_t } | Type::List { item_t } => item_t.as_ref().clone(),
+            t => panic!("expected array type, got {}", t),
         }
     }
 }
@@ -40,7 +43,10 @@ impl GetType for Entity {
         match self {
             Entity::Null { uuid, t } => t.clone(),
             Entity::Int { .. } => Type::Int,
+            Entity::Long { .. } => Type::Long,
             Entity::Bool { .. } => Type::Bool,
+            Entity::Char { .. } => Type::Char,
+            Entity::Double { .. } => Type::Double,
             Entity::Register { n: _, t } => t.clone(),
             Entity::NamedRegister { name: _, t } => t.clone(),
             Entity::GlobalConst { name: _, t } => t.clone(),
@@ -61,11 +67,15 @@ pub enum InstructionKind {
     BinaryOp { op: BinaryOperator, l: Entity, r: Entity },
     Call { func: String, args: Vec<Entity> },
     CallReference { func: Entity, args: Vec<Entity> },
+    /// call of a variadic function, the first fixed_args arguments are the declared ones
+    CallVariadic { func: String, fixed_args: usize, args: Vec<Entity> },
     RetVal { val: Entity },
     RetVoid,
     JumpCond { cond: Entity, true_label: String, false_label: String },
     Jump { label: String },
+    Switch { val: Entity, default_label: String, cases: Vec<(Entity, String)> },
     Phi { args: Vec<(Entity, String)> },
+    Select { cond: Entity, val_true: Entity, val_false: Entity },
 }
 
 impl InstructionKind {
@@ -118,12 +128,15 @@ pub struct BasicBlock {
 }
 
 impl BasicBlock {
-    /// checks if the block ends with a return instruction
-    pub fn always_returns(&self) -> bool {
+    /// checks if the block ends with a terminator instruction (return or jump)
+    pub fn is_terminated(&self) -> bool {
         if let Some(last_instr) = self.instructions.last() {
             match &last_instr.item {
                 InstructionKind::RetVoid => true,
                 InstructionKind::RetVal { .. } => true,
+                InstructionKind::Jump { .. } => true,
+                InstructionKind::JumpCond { .. } => true,
+                InstructionKind::Switch { .. } => true,
                 _ => false,
             }
         } else {
@@ -131,13 +144,6 @@ impl BasicBlock {
         }
     }
 
-    /// get the lowest register number used within the block
-    pub fn get_first_register(&self) -> Option<Entity> {
-        self.instructions.iter()
-            .filter_map(|i| i.get_meta().clone())
-            .nth(0)
-    }
-
     /// get block label name, or unnamed label 0
     pub fn get_label(&self) -> String {
         if let Some(label) = &self.label {
@@ -179,6 +185,9 @@ pub struct VTableDecl {
     /// name of the constant value holding initial vtable data
     pub data_const_name: String,
 
+    /// unique id of the class, stored as the first vtable item (before methods)
+    pub class_id: i32,
+
     /// vector of method type and name (declarations)
     pub methods: Vec<(Type, String)>,
 
@@ -190,15 +199,35 @@ impl VTableDecl {
     pub fn llvm_name(&self) -> String {
         format!("%{}", self.name)
     }
+
+    /// index of the method pointer in the vtable structure, the class id comes first
+    pub fn method_item_idx(&self, method: &String) -> i32 {
+        self.method_env.get(method).unwrap() + 1
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct StringDecl {
     pub name: String,
-    pub val: String,
+    /// string content with escape sequences replaced, without the trailing zero
+    pub bytes: Vec<u8>,
     pub len: usize,
 }
 
+#[derive(Debug, Clone)]
+pub struct GlobalDecl {
+    pub name: String,
+    /// type of the stored value (the global itself is a pointer to it)
+    pub t: Type,
+}
+
+impl GlobalDecl {
+    /// get entity representing a pointer to the global variable
+    pub fn get_ptr(&self) -> Entity {
+        Entity::GlobalConst { name: self.name.clone(), t: self.t.reference() }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FunctionDef {
     pub name: String,
@@ -213,5 +242,6 @@ pub enum LLVM {
     DeclStruct { decl: StructDecl },
     DeclVTable { decl: VTableDecl },
     DeclString { decl: StringDecl },
+    DeclGlobal { decl: GlobalDecl },
     Function { def: FunctionDef },
 }
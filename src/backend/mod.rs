@@ -1,6 +1,7 @@
 use itertools::Itertools;
 
 use crate::frontend::ast::Program;
+use crate::frontend::natives::{get_native_functions, NativeFunction};
 use crate::meta::TypeMeta;
 
 use crate::backend::compiler::ProgramCompiler;
@@ -17,14 +18,46 @@ pub fn compile(program: Program<TypeMeta>) -> String {
         String::from("declare i8* @__builtin_method__str__init__(i32)"),
         String::from("declare i8* @__builtin_method__array__init__(i32)"),
         String::from("declare i8* @__builtin_method__str__concat__(i8*, i8*)"),
-        String::from("declare void @__func__printInt(i32)"),
-        String::from("declare void @__func__printString(i8*)"),
-        String::from("declare void @__func__error()"),
-        String::from("declare i32 @__func__readInt()"),
-        String::from("declare i8* @__func__readString()"),
+        String::from("declare i8* @__builtin_method__str__format__(i8*, ...)"),
+        String::from("declare i32 @__builtin_method__str__compare__(i8*, i8*)"),
+        String::from("declare void @__builtin_method__print__(i8*, ...)"),
+        String::from("declare i32 @__builtin_method__str__length__(i8*)"),
+        String::from("%__builtin_struct__list = type opaque"),
+        String::from("declare %__builtin_struct__list* @__builtin_method__list__init__(i32)"),
+        String::from("declare i32 @__builtin_method__list__size__(%__builtin_struct__list*)"),
+        String::from("declare i8* @__builtin_method__list__push__(%__builtin_struct__list*)"),
+        String::from("declare i8* @__builtin_method__list__pop__(%__builtin_struct__list*)"),
+        String::from("declare i8* @__builtin_method__list__at__(%__builtin_struct__list*, i32)"),
+        String::from("declare i8* @__builtin_method__list__insert__(%__builtin_struct__list*, i32)"),
+        String::from("declare i8* @__builtin_method__list__remove__(%__builtin_struct__list*, i32)"),
+        String::from("%__builtin_struct__map = type opaque"),
+        String::from("declare %__builtin_struct__map* @__builtin_method__map__init__(i1, i32)"),
+        String::from("declare i32 @__builtin_method__map__size__(%__builtin_struct__map*)"),
+        String::from("declare %__builtin_struct__list* @__builtin_method__map__keys__(%__builtin_struct__map*)"),
+        String::from("declare i8* @__builtin_method__map_int__put__(%__builtin_struct__map*, i32)"),
+        String::from("declare i8* @__builtin_method__map_int__get__(%__builtin_struct__map*, i32)"),
+        String::from("declare i1 @__builtin_method__map_int__containsKey__(%__builtin_struct__map*, i32)"),
+        String::from("declare void @__builtin_method__map_int__remove__(%__builtin_struct__map*, i32)"),
+        String::from("declare i8* @__builtin_method__map_str__put__(%__builtin_struct__map*, i8*)"),
+        String::from("declare i8* @__builtin_method__map_str__get__(%__builtin_struct__map*, i8*)"),
+        String::from("declare i1 @__builtin_method__map_str__containsKey__(%__builtin_struct__map*, i8*)"),
+        String::from("declare void @__builtin_method__map_str__remove__(%__builtin_struct__map*, i8*)"),
+        String::from("declare void @__builtin_method__exception__throw__(i8*)"),
+        String::from("declare i8* @__builtin_method__exception__get__()"),
+        String::from("declare void @__builtin_method__exception__clear__()"),
+        String::from("declare void @__builtin_method__exception__uncaught__()"),
+        String::from("declare void @__builtin_method__assert__fail__(i8*, i8*)"),
     ];
+    builtins.extend(get_native_functions().iter().map(declare_native_function));
     let mut compiler = ProgramCompiler::with_builtin_functions(&mut builtins);
     compiler.compile_program(program).iter()
         .map(|llvm| llvm.to_string())
         .join("\n")
 }
+
+/// declaration of a function implemented in the runtime, named differently than Latte functions
+/// so that the program can define its own functions with the same names
+fn declare_native_function(native: &NativeFunction) -> String {
+    let args = native.args.iter().join(", ");
+    format!("declare {} @__builtin_func__{}({})", native.ret, native.name, args)
+}
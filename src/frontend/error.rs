@@ -24,6 +24,14 @@ pub enum FrontendErrorKind {
     SystemError {
         message: String,
     },
+    /// dereference of a value which may be null, found by the optional null analysis
+    NullError {
+        message: String,
+    },
+    /// read of a variable which may not be assigned, found by the definite assignment analysis
+    InitializationError {
+        message: String,
+    },
 }
 
 impl fmt::Display for FrontendErrorKind {
@@ -44,6 +52,12 @@ impl fmt::Display for FrontendErrorKind {
             FrontendErrorKind::SystemError { message } => {
                 write!(f, "SystemError: {}", message)
             }
+            FrontendErrorKind::NullError { message } => {
+                write!(f, "NullError: {}", message)
+            }
+            FrontendErrorKind::InitializationError { message } => {
+                write!(f, "InitializationError: {}", message)
+            }
         }
     }
 }
@@ -4,27 +4,62 @@ use std::sync::Arc;
 
 use codemap::{CodeMap, File, Pos};
 
-use crate::frontend::preprocessor::{optimize_constants, organize_blocks};
+use crate::frontend::preprocessor::{optimize_constants, organize_blocks, process_asserts, strip_asserts};
 use crate::meta::{LocationMeta, Meta, MetaMapper, TypeMeta};
 
 use self::error::{FrontendError, FrontendErrorKind};
 pub use self::parser::ast;
-use self::parser::parse_program;
+use self::parser::{parse_program, ParsedProgram};
+use self::prelude::get_prelude;
 use self::preprocessor::{CharOffset, clean_comments};
 use self::typechecker::check_types;
 
 mod parser;
+mod prelude;
 mod preprocessor;
 mod typechecker;
 
 pub mod error;
+pub mod natives;
 
 
 pub type CheckedProgram = ast::Program<TypeMeta>;
 pub type Error = FrontendError<String>;
 
-/// load file from path and process it as a source code
-pub fn process_file(path: String) -> Result<CheckedProgram, Vec<Error>> {
+pub type Warning = FrontendError<String>;
+
+/// options changing the way the source code is processed
+#[derive(Debug, Clone)]
+pub struct FrontendOptions {
+    /// remove assert statements from the program
+    pub strip_asserts: bool,
+    /// report dereferences of values which may be null
+    pub null_checks: CheckLevel,
+    /// report reads of variables declared without initialization before they are assigned
+    pub initialization_checks: CheckLevel,
+}
+
+impl Default for FrontendOptions {
+    fn default() -> Self {
+        FrontendOptions {
+            strip_asserts: false,
+            null_checks: CheckLevel::Disabled,
+            initialization_checks: CheckLevel::Warning,
+        }
+    }
+}
+
+/// the way problems found by an optional analysis are reported
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CheckLevel {
+    #[default]
+    Disabled,
+    Warning,
+    Error,
+}
+
+/// load file from path and process it as a source code, returns the program with warnings
+pub fn process_file(path: String, options: &FrontendOptions) -> Result<(CheckedProgram, Vec<Warning>), Vec<Error>> {
     let source_code = match fs::read_to_string(path.clone()) {
         Ok(source_code) => source_code,
         Err(e) => {
@@ -35,11 +70,32 @@ pub fn process_file(path: String) -> Result<CheckedProgram, Vec<Error>> {
             return Err(vec![err]);
         }
     };
-    process_code(path, source_code)
+    process_code_with_options(path, source_code, options)
 }
 
-/// process source code of the file given by name
+/// process source code of the file given by name, using default options (warnings are ignored)
 pub fn process_code(file_name: String, source_code: String) -> Result<CheckedProgram, Vec<Error>> {
+    process_code_with_options(file_name, source_code, &FrontendOptions::default())
+        .map(|(program, _)| program)
+}
+
+/// process source code of the file given by name, the result includes the standard library prelude
+/// and warnings about the code that is correct, but likely to fail
+pub fn process_code_with_options(
+    file_name: String, source_code: String, options: &FrontendOptions,
+) -> Result<(CheckedProgram, Vec<Warning>), Vec<Error>> {
+    let prelude = get_prelude();
+    let (_, mut program, warnings) = process_source(file_name, source_code, options, &prelude.declarations)?;
+    program.link(&prelude.program);
+    Ok((program, warnings))
+}
+
+/// process source code of the file given by name, which can use functions and classes
+/// declared in the (already checked) library, returns both parsed and checked program with warnings
+fn process_source(
+    file_name: String, source_code: String, options: &FrontendOptions,
+    library: &ParsedProgram,
+) -> Result<(ParsedProgram, CheckedProgram, Vec<Warning>), Vec<Error>> {
     // setup codemap for mapping byte offset to (file, line, column)
     let mut codemap = CodeMap::new();
     let codemap_file = codemap.add_file(
@@ -47,16 +103,30 @@ pub fn process_code(file_name: String, source_code: String) -> Result<CheckedPro
         source_code.clone(),
     );
     let (clean_code, source_map) = clean_comments(source_code);
+    let locate = |loc: &LocationMeta| {
+        codemap.map_meta(&codemap_file.map_meta(&source_map.map_meta(loc)))
+    };
 
     // perform all frontend actions
     let result = parse_program(clean_code)
+        .and_then(|p| process_asserts(p, &locate))
         .and_then(|p| optimize_constants(p))
         .and_then(|p| organize_blocks(p))
-        .and_then(|p| check_types(p));
+        .and_then(|p| check_types(p.clone(), library, options).map(|(checked, warnings)| (p, checked, warnings)))
+        .and_then(|(p, checked, warnings)| {
+            // disabled asserts are removed only after they are typechecked
+            let checked = if options.strip_asserts { strip_asserts(checked)? } else { checked };
+            Ok((p, checked, warnings))
+        });
 
-    // process results, mapping errors to their locations in the source code
+    // process results, mapping errors and warnings to their locations in the source code
     match result {
-        Ok(program) => Ok(program),
+        Ok((parsed, checked, warnings)) => {
+            let located_warnings: Vec<_> = warnings.iter()
+                .map(|w| locate_error(w, &source_map, &codemap_file, &codemap))
+                .collect();
+            Ok((parsed, checked, located_warnings))
+        }
         Err(errors) => {
             let located_errors: Vec<_> = errors.iter()
                 .map(|e| locate_error(&e, &source_map, &codemap_file, &codemap))
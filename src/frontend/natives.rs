@@ -0,0 +1,58 @@
+use crate::frontend::ast::Type;
+
+/// function implemented in the runtime library, called from Latte like any other function
+#[derive(Debug, Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub args: Vec<Type>,
+    pub ret: Type,
+}
+
+impl NativeFunction {
+    fn new(name: &str, args: Vec<Type>, ret: Type) -> Self {
+        Self { name: String::from(name), args, ret }
+    }
+
+    /// type of the function, as seen by the typechecker
+    pub fn get_type(&self) -> Type {
+        Type::Function {
+            args: self.args.iter().cloned().map(Box::new).collect(),
+            ret: Box::new(self.ret.clone()),
+        }
+    }
+}
+
+/// all functions implemented in the runtime library, this is the only place where they are listed:
+/// the typechecker gets their types and the backend generates their declarations from this table
+pub fn get_native_functions() -> Vec<NativeFunction> {
+    let str_array = Type::Array { item_t: Box::new(Type::Str) };
+    vec![
+        NativeFunction::new("printInt", vec![Type::Int], Type::Void),
+        NativeFunction::new("printString", vec![Type::Str], Type::Void),
+        NativeFunction::new("readInt", vec![], Type::Int),
+        NativeFunction::new("readString", vec![], Type::Str),
+        NativeFunction::new("error", vec![], Type::Void),
+        NativeFunction::new("printChar", vec![Type::Char], Type::Void),
+        NativeFunction::new("charToInt", vec![Type::Char], Type::Int),
+        NativeFunction::new("intToChar", vec![Type::Int], Type::Char),
+        NativeFunction::new("printLong", vec![Type::Long], Type::Void),
+        NativeFunction::new("readLong", vec![], Type::Long),
+        NativeFunction::new("intToLong", vec![Type::Int], Type::Long),
+        NativeFunction::new("longToInt", vec![Type::Long], Type::Int),
+        NativeFunction::new("printDouble", vec![Type::Double], Type::Void),
+        NativeFunction::new("readDouble", vec![], Type::Double),
+        NativeFunction::new("intToDouble", vec![Type::Int], Type::Double),
+        NativeFunction::new("doubleToInt", vec![Type::Double], Type::Int),
+        NativeFunction::new("sqrt", vec![Type::Double], Type::Double),
+        NativeFunction::new("floor", vec![Type::Double], Type::Double),
+        NativeFunction::new("pow", vec![Type::Double, Type::Double], Type::Double),
+        NativeFunction::new("charAt", vec![Type::Str, Type::Int], Type::Char),
+        NativeFunction::new("substring", vec![Type::Str, Type::Int, Type::Int], Type::Str),
+        NativeFunction::new("indexOf", vec![Type::Str, Type::Str], Type::Int),
+        NativeFunction::new("startsWith", vec![Type::Str, Type::Str], Type::Bool),
+        NativeFunction::new("split", vec![Type::Str, Type::Str], str_array),
+        NativeFunction::new("intToString", vec![Type::Int], Type::Str),
+        NativeFunction::new("stringToInt", vec![Type::Str], Type::Int),
+        NativeFunction::new("trim", vec![Type::Str], Type::Str),
+    ]
+}
@@ -35,19 +35,35 @@ pub enum BinaryOperator {
     NotEqual,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    /// arithmetic shift (preserves the sign)
+    ShiftRight,
+    /// logical shift (fills with zeros)
+    ShiftRightLogical,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum UnaryOperator {
     Neg,
     Not,
+    BitNot,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ExpressionKind<MetaT> {
     LitInt { val: i32 },
+    LitLong { val: i64 },
     LitBool { val: bool },
+    /// ascii value of the character
+    LitChar { val: u8 },
+    LitDouble { val: f64 },
     LitStr { val: String },
+    /// string literal with interpolated expressions ("x = ${x}"), parts are the raw text fragments
+    /// between the expressions (without quotes), so there is always one more part than expressions
+    Interpolation { parts: Vec<String>, exprs: Vec<Box<Expression<MetaT>>> },
     LitNull,
     App { r: Reference<MetaT>, args: Vec<Box<Expression<MetaT>>> },
     Unary { op: UnaryOperator, arg: Box<Expression<MetaT>> },
@@ -58,7 +74,19 @@ pub enum ExpressionKind<MetaT> {
     },
     InitDefault { t: Type },
     InitArr { t: Type, size: Box<Expression<MetaT>> },
+    /// array literal, t is the type of entire array (for literals in declarations
+    /// it is the declared type)
+    LitArr { t: Type, items: Vec<Box<Expression<MetaT>>> },
     Reference { r: Reference<MetaT> },
+    /// increment or decrement used as an expression, its value is taken before (postfix)
+    /// or after (prefix) the change
+    Mut { r: Reference<MetaT>, op: StatementOp, prefix: bool },
+    /// conditional (ternary) expression, only one of the branches is evaluated
+    Cond {
+        cond: Box<Expression<MetaT>>,
+        expr_true: Box<Expression<MetaT>>,
+        expr_false: Box<Expression<MetaT>>,
+    },
     // edge case: expr in Cast is always null so we don't need to locate it:
     Cast { t: Type, expr: Box<Expression<MetaT>> },
     Error,
@@ -66,25 +94,185 @@ pub enum ExpressionKind<MetaT> {
 
 pub type Expression<MetaT> = AstItem<ExpressionKind<MetaT>, MetaT>;
 
+impl<MetaT> ExpressionKind<MetaT> {
+    /// checks if the expression is a literal of a primitive type or string, which can be a value of a constant
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            ExpressionKind::LitInt { .. }
+            | ExpressionKind::LitLong { .. }
+            | ExpressionKind::LitBool { .. }
+            | ExpressionKind::LitChar { .. }
+            | ExpressionKind::LitDouble { .. }
+            | ExpressionKind::LitStr { .. }
+        )
+    }
+}
+
+/// value of the character following a backslash in an escape sequence
+fn unescape_char(c: char) -> Option<char> {
+    match c {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '\\' => Some('\\'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        _ => None,
+    }
+}
+
+/// converts character literal from the source code (in quotes, possibly escaped)
+/// to its ascii value, returns None for non-ascii characters and unknown escape sequences
+pub fn parse_char_literal(literal: &str) -> Option<u8> {
+    let content = &literal[1..literal.len() - 1];
+    let mut chars = content.chars();
+    let c = match (chars.next(), chars.next(), chars.next()) {
+        (Some('\\'), Some(escaped), None) => unescape_char(escaped)?,
+        (Some(c), None, None) => c,
+        _ => return None,
+    };
+    if c.is_ascii() {
+        Some(c as u8)
+    } else {
+        None
+    }
+}
+
+/// converts string literal from the source code (in quotes) to the bytes it represents,
+/// unknown escape sequences are kept unchanged
+pub fn unescape_string_literal(literal: &str) -> Vec<u8> {
+    let content = &literal[1..literal.len() - 1];
+    let mut unescaped = String::new();
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+        } else if let Some(escaped) = chars.next() {
+            match unescape_char(escaped) {
+                Some(unescaped_char) => unescaped.push(unescaped_char),
+                None => {
+                    unescaped.push(c);
+                    unescaped.push(escaped);
+                }
+            }
+        }
+    }
+    unescaped.into_bytes()
+}
+
+/// single conversion in a printf format string, eg. `%-5d`
+#[derive(Debug, PartialEq, Clone)]
+pub struct FormatSpec {
+    /// flags, width and precision, passed to the runtime unchanged
+    pub modifiers: String,
+    /// d (int or long), f, e, g (double), c (char) or s (string, boolean or object)
+    pub conversion: char,
+}
+
+impl FormatSpec {
+    /// types of values that can be formatted with this conversion (objects are checked separately)
+    pub fn accepts(&self, t: &Type) -> bool {
+        matches!(
+            (self.conversion, t),
+            ('d', Type::Int) | ('d', Type::Long)
+            | ('f', Type::Double) | ('e', Type::Double) | ('g', Type::Double)
+            | ('c', Type::Char)
+            | ('s', Type::Str) | ('s', Type::Bool) | ('s', Type::Class { .. })
+        )
+    }
+
+    /// type reported when the formatted value has incorrect type
+    pub fn expected_type(&self) -> Type {
+        match self.conversion {
+            'd' => Type::Int,
+            'c' => Type::Char,
+            's' => Type::Str,
+            _ => Type::Double,
+        }
+    }
+}
+
+/// splits printf format string literal (in quotes) into the raw text fragments and conversions,
+/// there is always one more fragment than conversions, `%%` is kept in the fragments
+pub fn parse_format_string(literal: &str) -> Result<(Vec<String>, Vec<FormatSpec>), String> {
+    let content = &literal[1..literal.len() - 1];
+    let mut parts = Vec::new();
+    let mut specs = Vec::new();
+    let mut part = String::new();
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            part.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            part.push_str("%%");
+            continue;
+        }
+        let mut modifiers = String::new();
+        while let Some(&m) = chars.peek() {
+            if "-+ 0#.".contains(m) || m.is_ascii_digit() {
+                modifiers.push(m);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        match chars.next() {
+            Some(conversion) if "dfegcs".contains(conversion) => {
+                parts.push(part);
+                part = String::new();
+                specs.push(FormatSpec { modifiers, conversion });
+            }
+            Some(conversion) => {
+                return Err(format!("Unknown conversion %{}{} in format string", modifiers, conversion));
+            }
+            None => {
+                return Err(String::from("Incomplete conversion at the end of format string"));
+            }
+        }
+    }
+    parts.push(part);
+    Ok((parts, specs))
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Type {
     /// basic types
     Int,
+    Long,
     Str,
     Bool,
+    Char,
+    Double,
     Void,
     Null,
+    /// type of statements that never complete normally (throw)
+    Never,
 
     /// complex types (extensions)
     Class { ident: String },
     Array { item_t: Box<Type> },
+    /// growable list, implemented in the runtime library
+    List { item_t: Box<Type> },
+    /// hash map with int or string keys, implemented in the runtime library
+    Map { key_t: Box<Type>, val_t: Box<Type> },
 
     /// used for checking types during function call
     Function { args: Vec<Box<Type>>, ret: Box<Type> },
+    /// builtin function accepting any number of printable values after the fixed arguments,
+    /// formatted function has a format string literal as the last fixed argument
+    VariadicFunction { args: Vec<Box<Type>>, ret: Box<Type>, formatted: bool },
 
     /// error type for smooth propagation of errors during parsing
     Error,
 
+    /// type of a variable declared with var, replaced by the type of its initializer in typechecker
+    Infer,
+
     /// represents any type, used to report errors like: 'expected Array { Any } got Int'
     Any,
 
@@ -103,6 +291,16 @@ impl Type {
     pub fn reference(&self) -> Self {
         Type::Reference { t: Box::new(self.clone()) }
     }
+
+    /// numeric types support arithmetic operators
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Long | Type::Double)
+    }
+
+    /// integral types support increments, bitwise and shift operators
+    pub fn is_integral(&self) -> bool {
+        matches!(self, Type::Int | Type::Long)
+    }
 }
 
 impl Default for Type {
@@ -121,6 +319,16 @@ pub enum ReferenceKind<MetaT> {
 
     // after typechecker mapping:
     ArrayLen { ident: String },
+    /// self_cls is the class of self if the string is its field
+    StrLen { ident: String, self_cls: Option<String> },
+    /// self_cls is the class of self if the string is its field
+    StrChar { ident: String, idx: Box<Expression<MetaT>>, self_cls: Option<String> },
+    /// builtin function which is not shadowed by any function of the program
+    Builtin { ident: String },
+    /// self_cls is the class of self if the list is its field
+    ListMethod { ident: String, method: String, self_cls: Option<String> },
+    /// self_cls is the class of self if the map is its field
+    MapMethod { ident: String, method: String, self_cls: Option<String> },
     TypedObject { obj: String, cls: String, field: String },
     TypedMemberObject { self_cls: String, obj: String, obj_class: String, field: String }
 }
@@ -135,6 +343,20 @@ pub enum DeclItemKind<MetaT> {
 
 pub type DeclItem<MetaT> = AstItem<DeclItemKind<MetaT>, MetaT>;
 
+impl<MetaT> DeclItemKind<MetaT> {
+    /// array literal without explicit type ({1, 2, 3}) is parsed with Type::Error,
+    /// it takes the type of the declaration it initializes
+    pub fn set_declared_type(&mut self, t: &Type) {
+        if let DeclItemKind::Init { val, .. } = self {
+            if let ExpressionKind::LitArr { t: lit_t, .. } = &mut val.item {
+                if *lit_t == Type::Error {
+                    *lit_t = t.clone();
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct BlockItem<MetaT> {
     pub stmts: Vec<Box<Statement<MetaT>>>
@@ -146,9 +368,10 @@ pub type Block<MetaT> = AstItem<BlockItem<MetaT>, MetaT>;
 pub enum StatementKind<MetaT> {
     Block { block: Block<MetaT> },
     Empty,
-    Decl { t: Type, items: Vec<DeclItem<MetaT>> },
+    Decl { t: Type, items: Vec<DeclItem<MetaT>>, is_final: bool },
     Ass { r: Reference<MetaT>, expr: Box<Expression<MetaT>> },
     Mut { r: Reference<MetaT>, op: StatementOp },
+    AssOp { r: Reference<MetaT>, op: BinaryOperator, expr: Box<Expression<MetaT>> },
     Return { expr: Option<Box<Expression<MetaT>>> },
     Cond { expr: Box<Expression<MetaT>>, stmt: Box<Statement<MetaT>> },
     CondElse {
@@ -156,19 +379,74 @@ pub enum StatementKind<MetaT> {
         stmt_true: Box<Statement<MetaT>>,
         stmt_false: Box<Statement<MetaT>>,
     },
-    While { expr: Box<Expression<MetaT>>, stmt: Box<Statement<MetaT>> },
+    While {
+        label: Option<String>,
+        expr: Box<Expression<MetaT>>,
+        stmt: Box<Statement<MetaT>>,
+    },
     For {
+        label: Option<String>,
         t: Type,
         ident: String,
         arr: Box<Expression<MetaT>>,
         stmt: Box<Statement<MetaT>>,
     },
+    ForLoop {
+        label: Option<String>,
+        init: Option<Box<Statement<MetaT>>>,
+        cond: Option<Box<Expression<MetaT>>>,
+        step: Option<Box<Statement<MetaT>>>,
+        stmt: Box<Statement<MetaT>>,
+    },
+    DoWhile {
+        label: Option<String>,
+        stmt: Box<Statement<MetaT>>,
+        expr: Box<Expression<MetaT>>,
+    },
+    Switch {
+        expr: Box<Expression<MetaT>>,
+        cases: Vec<SwitchCase<MetaT>>,
+        default: Option<Block<MetaT>>,
+    },
+    Break { label: Option<String> },
+    Continue { label: Option<String> },
     Expr { expr: Box<Expression<MetaT>> },
+    Throw { expr: Box<Expression<MetaT>> },
+    /// location (file:line:column) is filled in by the preprocessor, to be included in the failure message
+    Assert {
+        expr: Box<Expression<MetaT>>,
+        message: Option<Box<Expression<MetaT>>>,
+        location: String,
+    },
+    Try {
+        block: Block<MetaT>,
+        catches: Vec<CatchClause<MetaT>>,
+        finally: Option<Block<MetaT>>,
+    },
     Error,
 }
 
 pub type Statement<MetaT> = AstItem<StatementKind<MetaT>, MetaT>;
 
+/// single `case` of the switch statement, matching any of the constant values
+#[derive(Debug, PartialEq, Clone)]
+pub struct SwitchCaseItem<MetaT> {
+    pub vals: Vec<Box<Expression<MetaT>>>,
+    pub block: Block<MetaT>,
+}
+
+pub type SwitchCase<MetaT> = AstItem<SwitchCaseItem<MetaT>, MetaT>;
+
+/// single `catch` clause of the try statement, binding the caught exception to a variable
+#[derive(Debug, PartialEq, Clone)]
+pub struct CatchClauseItem<MetaT> {
+    pub t: Type,
+    pub ident: String,
+    pub block: Block<MetaT>,
+}
+
+pub type CatchClause<MetaT> = AstItem<CatchClauseItem<MetaT>, MetaT>;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum StatementOp {
     Increment,
@@ -176,7 +454,7 @@ pub enum StatementOp {
 }
 
 #[derive(Debug, PartialEq, Hash, Clone)]
-pub struct ArgItem { pub t: Type, pub ident: String }
+pub struct ArgItem { pub t: Type, pub ident: String, pub is_final: bool }
 
 pub type Arg<MetaT> = AstItem<ArgItem, MetaT>;
 
@@ -192,6 +470,8 @@ pub struct FunctionItem<MetaT> {
     pub ident: String,
     pub args: Vec<Arg<MetaT>>,
     pub block: Block<MetaT>,
+    /// extern functions are implemented outside of Latte, their block is always empty
+    pub is_extern: bool,
 }
 
 pub type Function<MetaT> = AstItem<FunctionItem<MetaT>, MetaT>;
@@ -204,7 +484,12 @@ impl<MetaT: Clone> FunctionItem<MetaT> {
         Env::<Arg<MetaT>>::from_vec(&mut args.clone())?;
 
         // insert actual vector into the Function to preserve order
-        Ok(Self { ret, ident, args, block })
+        Ok(Self { ret, ident, args, block, is_extern: false })
+    }
+
+    pub fn as_extern(&mut self) -> Self {
+        self.is_extern = true;
+        self.clone()
     }
 
     pub fn get_type(&self) -> Type {
@@ -221,15 +506,19 @@ impl<MetaT> Keyed for FunctionItem<MetaT> {
     }
 }
 
+/// instance variable of a class, it can have a constant initial value (assigned when an object is created),
+/// final variables cannot be assigned after that
 #[derive(Debug, PartialEq, Clone)]
-pub struct ClassVarItem {
+pub struct ClassVarItem<MetaT> {
     pub t: Type,
     pub ident: String,
+    pub is_final: bool,
+    pub val: Option<Box<Expression<MetaT>>>,
 }
 
-pub type ClassVar<MetaT> = AstItem<ClassVarItem, MetaT>;
+pub type ClassVar<MetaT> = AstItem<ClassVarItem<MetaT>, MetaT>;
 
-impl Keyed for ClassVarItem {
+impl<MetaT> Keyed for ClassVarItem<MetaT> {
     fn get_key(&self) -> &String {
         &self.ident
     }
@@ -267,10 +556,38 @@ impl<MetaT> Keyed for ClassItem<MetaT> {
     }
 }
 
+/// variable declared at the top level of the program, visible in all functions and methods,
+/// constants have to be initialized with an expression that can be evaluated during compilation
+#[derive(Debug, PartialEq, Clone)]
+pub struct GlobalItem<MetaT> {
+    pub t: Type,
+    pub decl: DeclItemKind<MetaT>,
+    pub is_const: bool,
+}
+
+pub type Global<MetaT> = AstItem<GlobalItem<MetaT>, MetaT>;
+
+impl<MetaT> GlobalItem<MetaT> {
+    pub fn new(t: Type, decl: DeclItemKind<MetaT>, is_const: bool) -> Self {
+        let mut decl = decl;
+        decl.set_declared_type(&t);
+        Self { t, decl, is_const }
+    }
+}
+
+impl<MetaT> Keyed for GlobalItem<MetaT> {
+    fn get_key(&self) -> &String {
+        match &self.decl {
+            DeclItemKind::NoInit { ident } | DeclItemKind::Init { ident, .. } => ident,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TopDefKind<MetaT> {
     Function { func: Function<MetaT> },
     Class { cls: Class<MetaT> },
+    Global { global: Global<MetaT> },
     Error,
 }
 
@@ -281,12 +598,40 @@ pub type TopDef<MetaT> = AstItem<TopDefKind<MetaT>, MetaT>;
 pub struct Program<MetaT> {
     pub classes: Env<Class<MetaT>>,
     pub functions: Env<Function<MetaT>>,
+    /// global variables are kept in the order of declaration, in which they are initialized
+    pub globals: Vec<Global<MetaT>>,
 }
 
 impl<MetaT: Debug + Clone> Program<MetaT> {
-    pub fn new(classes: &mut Vec<Class<MetaT>>, functions: &mut Vec<Function<MetaT>>) -> Result<Self, Vec<FrontendError<MetaT>>> {
+    pub fn new(
+        classes: &mut Vec<Class<MetaT>>, functions: &mut Vec<Function<MetaT>>, globals: Vec<Global<MetaT>>,
+    ) -> Result<Self, Vec<FrontendError<MetaT>>> {
         let classes = Env::<Class<MetaT>>::from_vec(classes)?;
         let functions = Env::<Function<MetaT>>::from_vec(functions)?;
-        Ok(Self { classes, functions })
+        Env::<Global<MetaT>>::from_vec(&mut globals.clone())?;
+        Ok(Self { classes, functions, globals })
+    }
+
+    /// get global variable declaration by its identifier
+    pub fn get_global(&self, ident: &String) -> Option<&Global<MetaT>> {
+        self.globals.iter().find(|global| global.item.get_key() == ident)
+    }
+
+    /// adds classes, functions and globals of the library to the program,
+    /// definitions of the program take precedence over the library ones with the same names
+    pub fn link(&mut self, library: &Program<MetaT>) {
+        for (ident, cls) in library.classes.iter() {
+            self.classes.entry(ident.clone()).or_insert_with(|| cls.clone());
+        }
+        for (ident, func) in library.functions.iter() {
+            self.functions.entry(ident.clone()).or_insert_with(|| func.clone());
+        }
+        // library globals are initialized first
+        let mut globals: Vec<_> = library.globals.iter()
+            .filter(|global| self.get_global(global.item.get_key()).is_none())
+            .cloned()
+            .collect();
+        globals.append(&mut self.globals);
+        self.globals = globals;
     }
 }
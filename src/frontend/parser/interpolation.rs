@@ -0,0 +1,125 @@
+use lalrpop_util::{ErrorRecovery, ParseError as LalrpopError};
+
+use crate::frontend::ast::{Expression, ExpressionKind};
+use crate::frontend::error::{FrontendError, FrontendErrorKind};
+use crate::meta::LocationMeta;
+
+use super::latte::InterpolatedExprParser;
+
+/// converts string literal from the source code (in quotes) located at the given offset
+/// to LitStr, or to Interpolation if it contains any `${...}` expressions
+///
+/// interpolated expressions are parsed with a separate parser, `\$` can be used to
+/// put a dollar sign before a brace without starting the interpolation
+pub fn parse_string_literal(
+    errors: &mut Vec<FrontendError<LocationMeta>>, literal: &str, offset: usize,
+) -> ExpressionKind<LocationMeta> {
+    let bytes = literal.as_bytes();
+    let end = literal.len() - 1;  // position of the closing quote
+
+    let mut parts = Vec::new();
+    let mut exprs = Vec::new();
+    let mut part = String::new();
+    let mut part_start = 1;
+    let mut idx = 1;
+    while idx < end {
+        match (bytes[idx], bytes[idx + 1]) {
+            (b'\\', b'$') => {
+                // drop the backslash, dollar sign is copied with the rest of the part
+                part.push_str(&literal[part_start..idx]);
+                part_start = idx + 1;
+                idx += 2;
+            }
+            (b'\\', _) => {
+                idx += 2;
+            }
+            (b'$', b'{') => {
+                part.push_str(&literal[part_start..idx]);
+                parts.push(part);
+                part = String::new();
+
+                let expr_start = idx + 2;
+                let expr_end = find_closing_brace(bytes, expr_start);
+                exprs.push(parse_interpolated_expr(errors, literal, offset, expr_start, expr_end));
+                idx = expr_end + 1;
+                part_start = idx;
+            }
+            _ => {
+                idx += 1;
+            }
+        }
+    }
+    part.push_str(&literal[part_start..end]);
+    parts.push(part);
+
+    if exprs.is_empty() {
+        ExpressionKind::LitStr { val: format!("\"{}\"", parts.remove(0)) }
+    } else {
+        ExpressionKind::Interpolation { parts, exprs }
+    }
+}
+
+/// converts string literal used as a case value, which has to be a constant (cannot be interpolated)
+pub fn parse_case_string_literal(
+    errors: &mut Vec<FrontendError<LocationMeta>>, literal: &str, offset: usize,
+) -> ExpressionKind<LocationMeta> {
+    match parse_string_literal(errors, literal, offset) {
+        ExpressionKind::Interpolation { .. } => {
+            let kind = FrontendErrorKind::ParseError {
+                message: String::from("Case value must be a constant")
+            };
+            errors.push(FrontendError::new(kind, LocationMeta::from(offset)));
+            ExpressionKind::Error
+        }
+        kind => kind,
+    }
+}
+
+/// position of the brace closing the interpolated expression, the lexer guarantees that it exists
+/// and that the only strings in the expression are string literals without interpolation
+fn find_closing_brace(bytes: &[u8], start: usize) -> usize {
+    let mut in_str = false;
+    let mut idx = start;
+    loop {
+        match (in_str, bytes[idx]) {
+            (false, b'}') => return idx,
+            (_, b'"') => in_str = !in_str,
+            (true, b'\\') => idx += 1,
+            _ => (),
+        }
+        idx += 1;
+    }
+}
+
+fn parse_interpolated_expr(
+    errors: &mut Vec<FrontendError<LocationMeta>>, literal: &str, offset: usize, start: usize, end: usize,
+) -> Box<Expression<LocationMeta>> {
+    let location = LocationMeta::from(offset + start);
+    let code = &literal[start..end];
+    if code.trim().is_empty() {
+        let kind = FrontendErrorKind::ParseError {
+            message: String::from("Empty expression in string interpolation")
+        };
+        errors.push(FrontendError::new(kind, location.clone()));
+        return Box::new(Expression::new(ExpressionKind::Error, location));
+    }
+
+    // the expression is preceded with whitespace, so that the parser reports
+    // locations of its nodes and errors relative to the beginning of the whole program
+    let padded_code = " ".repeat(offset + start) + code;
+    match InterpolatedExprParser::new().parse(errors, &padded_code) {
+        Ok(expr) => expr,
+        Err(LalrpopError::UnrecognizedEOF { location: eof_location, expected: _ }) => {
+            // the end of input is the closing brace, not the end of file
+            let kind = FrontendErrorKind::ParseError {
+                message: String::from("Unexpected end of interpolated expression")
+            };
+            errors.push(FrontendError::new(kind, LocationMeta::from(eof_location)));
+            Box::new(Expression::new(ExpressionKind::Error, location))
+        }
+        Err(error) => {
+            errors.push(FrontendError::from(ErrorRecovery { error, dropped_tokens: vec![] }));
+            Box::new(Expression::new(ExpressionKind::Error, location))
+        }
+    }
+}
@@ -1,9 +1,12 @@
+use lalrpop_util::ErrorRecovery;
+
 use crate::frontend::error::FrontendError;
 use crate::meta::LocationMeta;
 
 use self::ast::Program;
 use self::latte::ProgramParser;
 
+mod interpolation;
 mod latte;
 
 pub mod ast;
@@ -24,7 +27,9 @@ pub fn parse_program(source_code: String) -> Result<ParsedProgram, ParserErrors>
                 Err(errors)
             }
         }
-        Err(_) => {
+        Err(error) => {
+            // unrecoverable errors (eg. invalid tokens) are reported after the recovered ones
+            errors.push(FrontendError::from(ErrorRecovery { error, dropped_tokens: vec![] }));
             Err(errors)
         }
     }
@@ -0,0 +1,50 @@
+use std::sync::OnceLock;
+
+use crate::util::env::Env;
+
+use super::{CheckedProgram, FrontendOptions, process_source};
+use super::parser::ParsedProgram;
+
+/// source code of the standard library, bundled into the compiler binary
+const PRELUDE_SOURCE: &str = include_str!("../../lib/prelude.lat");
+
+/// standard library written in Latte, implicitly available in every program
+pub struct Prelude {
+    /// declarations visible to the typechecker when checking the user program
+    pub declarations: ParsedProgram,
+    /// checked library code, linked with every user program
+    pub program: CheckedProgram,
+}
+
+/// prelude is processed only once, the first time it is needed
+pub fn get_prelude() -> &'static Prelude {
+    static PRELUDE: OnceLock<Prelude> = OnceLock::new();
+    PRELUDE.get_or_init(load_prelude)
+}
+
+fn load_prelude() -> Prelude {
+    let empty_library = ParsedProgram { classes: Env::new(), functions: Env::new(), globals: vec![] };
+    let result = process_source(
+        String::from("prelude.lat"),
+        String::from(PRELUDE_SOURCE),
+        &FrontendOptions::default(),
+        &empty_library,
+    );
+    match result {
+        Ok((declarations, program, _)) => Prelude { declarations, program },
+        // prelude is a part of the compiler, so this is a compiler bug, not a user error
+        Err(errors) => panic!("invalid prelude: {:?}", errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prelude_is_correct() {
+        let prelude = load_prelude();
+        assert!(prelude.program.functions.contains_key("max"));
+        assert!(prelude.program.classes.contains_key("StringBuilder"));
+    }
+}
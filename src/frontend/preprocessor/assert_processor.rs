@@ -0,0 +1,139 @@
+use std::fmt::Debug;
+
+use crate::frontend::ast::{Block, Class, Expression, Function, Global, Reference, Statement, StatementKind};
+use crate::frontend::error::FrontendError;
+use crate::meta::LocationMeta;
+use crate::util::mapper::AstMapper;
+
+/// fills in source locations of assert statements, or removes them when asserts are disabled
+pub struct AssertProcessor<'a, MetaT> {
+    /// true if asserts are replaced with empty statements
+    pub strip_asserts: bool,
+
+    /// translates location in the parsed code to the one in the original source file
+    pub locate: &'a dyn Fn(&MetaT) -> String,
+}
+
+type ProcessorResult<T> = Result<T, Vec<FrontendError<LocationMeta>>>;
+
+impl<MetaT: Clone + Debug> AssertProcessor<'_, MetaT> {
+    fn map_boxed_statement(&mut self, stmt: &Statement<MetaT>) -> ProcessorResult<Box<Statement<MetaT>>> {
+        Ok(Box::new(self.map_statement(stmt)?))
+    }
+}
+
+impl<MetaT: Clone + Debug> AstMapper<MetaT, MetaT, FrontendError<LocationMeta>> for AssertProcessor<'_, MetaT> {
+    fn map_var_reference(&mut self, r: &Reference<MetaT>) -> ProcessorResult<Reference<MetaT>> {
+        Ok(r.clone())
+    }
+
+    fn map_func_reference(&mut self, r: &Reference<MetaT>) -> ProcessorResult<Reference<MetaT>> {
+        Ok(r.clone())
+    }
+
+    fn map_block(&mut self, block: &Block<MetaT>) -> ProcessorResult<Block<MetaT>> {
+        let mut mapped_block = block.clone();
+        mapped_block.item.stmts = block.item.stmts.iter()
+            .map(|stmt| self.map_boxed_statement(stmt))
+            .collect::<ProcessorResult<_>>()?;
+        Ok(mapped_block)
+    }
+
+    fn map_expression(&mut self, expr: &Expression<MetaT>) -> ProcessorResult<Expression<MetaT>> {
+        Ok(expr.clone())
+    }
+
+    /// asserts can be nested in any compound statement, so all of them are visited
+    fn map_statement(&mut self, stmt: &Statement<MetaT>) -> ProcessorResult<Statement<MetaT>> {
+        let kind = match &stmt.item {
+            StatementKind::Assert { .. } if self.strip_asserts => StatementKind::Empty,
+            StatementKind::Assert { expr, message, location: _ } => StatementKind::Assert {
+                expr: expr.clone(),
+                message: message.clone(),
+                location: (self.locate)(stmt.get_meta()),
+            },
+            StatementKind::Block { block } => StatementKind::Block {
+                block: self.map_block(block)?
+            },
+            StatementKind::Cond { expr, stmt } => StatementKind::Cond {
+                expr: expr.clone(),
+                stmt: self.map_boxed_statement(stmt)?,
+            },
+            StatementKind::CondElse { expr, stmt_true, stmt_false } => StatementKind::CondElse {
+                expr: expr.clone(),
+                stmt_true: self.map_boxed_statement(stmt_true)?,
+                stmt_false: self.map_boxed_statement(stmt_false)?,
+            },
+            StatementKind::While { label, expr, stmt } => StatementKind::While {
+                label: label.clone(),
+                expr: expr.clone(),
+                stmt: self.map_boxed_statement(stmt)?,
+            },
+            StatementKind::For { label, t, ident, arr, stmt } => StatementKind::For {
+                label: label.clone(),
+                t: t.clone(),
+                ident: ident.clone(),
+                arr: arr.clone(),
+                stmt: self.map_boxed_statement(stmt)?,
+            },
+            StatementKind::ForLoop { label, init, cond, step, stmt } => StatementKind::ForLoop {
+                label: label.clone(),
+                init: init.clone(),
+                cond: cond.clone(),
+                step: step.clone(),
+                stmt: self.map_boxed_statement(stmt)?,
+            },
+            StatementKind::DoWhile { label, stmt, expr } => StatementKind::DoWhile {
+                label: label.clone(),
+                stmt: self.map_boxed_statement(stmt)?,
+                expr: expr.clone(),
+            },
+            StatementKind::Switch { expr, cases, default } => {
+                let mut mapped_cases = Vec::new();
+                for case in cases.iter() {
+                    let mut mapped_case = case.clone();
+                    mapped_case.item.block = self.map_block(&case.item.block)?;
+                    mapped_cases.push(mapped_case);
+                }
+                StatementKind::Switch {
+                    expr: expr.clone(),
+                    cases: mapped_cases,
+                    default: default.as_ref().map(|block| self.map_block(block)).transpose()?,
+                }
+            }
+            StatementKind::Try { block, catches, finally } => {
+                let mut mapped_catches = Vec::new();
+                for catch in catches.iter() {
+                    let mut mapped_catch = catch.clone();
+                    mapped_catch.item.block = self.map_block(&catch.item.block)?;
+                    mapped_catches.push(mapped_catch);
+                }
+                StatementKind::Try {
+                    block: self.map_block(block)?,
+                    catches: mapped_catches,
+                    finally: finally.as_ref().map(|block| self.map_block(block)).transpose()?,
+                }
+            }
+            kind => kind.clone(),
+        };
+        Ok(Statement::new(kind, stmt.get_meta().clone()))
+    }
+
+    fn map_class(&mut self, class: &Class<MetaT>) -> ProcessorResult<Class<MetaT>> {
+        let mut mapped_class = class.clone();
+        for (method_name, method) in class.item.methods.iter() {
+            mapped_class.item.methods.insert(method_name.clone(), self.map_function(method)?);
+        }
+        Ok(mapped_class)
+    }
+
+    fn map_function(&mut self, function: &Function<MetaT>) -> ProcessorResult<Function<MetaT>> {
+        let mut mapped_function = function.clone();
+        mapped_function.item.block = self.map_block(&function.item.block)?;
+        Ok(mapped_function)
+    }
+
+    fn map_global(&mut self, global: &Global<MetaT>) -> ProcessorResult<Global<MetaT>> {
+        Ok(global.clone())
+    }
+}
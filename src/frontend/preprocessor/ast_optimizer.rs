@@ -1,28 +1,198 @@
-use crate::frontend::ast::{Block, BlockItem, Class, ClassItem, Expression, ExpressionKind, Function, FunctionItem, Reference, ReferenceKind, Statement, StatementKind};
-use crate::frontend::error::{FrontendError, FrontendErrorKind};
-use crate::meta::{LocationMeta, Meta};
+use crate::frontend::ast::{BinaryOperator, Block, BlockItem, Class, DeclItemKind, Expression, ExpressionKind, Function, Global, Keyed, Program, Reference, ReferenceKind, Statement, StatementKind, UnaryOperator};
+use crate::frontend::error::FrontendError;
+use crate::meta::LocationMeta;
+use crate::util::env::Env;
 use crate::util::mapper::AstMapper;
 
-pub struct AstOptimizer;
+/// replaces constants with their values, evaluates operations on literals
+/// and removes conditional statements that are always true/false
+#[derive(Clone)]
+pub struct AstOptimizer {
+    /// values of the constants visible in the current scope (not shadowed by any variable)
+    constants: Env<ExpressionKind<LocationMeta>>,
+    /// names of the instance variables of each class, including the inherited ones
+    class_fields: Env<Vec<String>>,
+}
 
 type OptimizationResult<T> = Result<T, Vec<FrontendError<LocationMeta>>>;
 
+impl AstOptimizer {
+    pub fn new(program: &Program<LocationMeta>) -> Self {
+        let mut class_fields = Env::new();
+        for (class_name, class) in program.classes.iter() {
+            let mut fields = Vec::new();
+            let mut current = Some(class);
+            while let Some(cls) = current {
+                fields.extend(cls.item.vars.keys().cloned());
+                current = cls.item.parent.as_ref().and_then(|parent| program.classes.get(parent));
+            }
+            class_fields.insert(class_name.clone(), fields);
+        }
+        Self { constants: Env::new(), class_fields }
+    }
+
+    /// creates optimizer for a nested scope, where given variables shadow constants with the same name
+    fn with_variables<'a>(&self, idents: impl Iterator<Item=&'a String>) -> Self {
+        let mut optimizer = self.clone();
+        for ident in idents {
+            optimizer.constants.remove(ident);
+        }
+        optimizer
+    }
+
+    /// creates optimizer for the scope following the declaration, where declared variables shadow constants,
+    /// except final variables initialized with literals, which become constants themselves
+    fn with_declaration(&self, stmt: &Statement<LocationMeta>) -> Self {
+        let mut optimizer = self.with_variables(Self::get_declared_variables(stmt).iter());
+        if let StatementKind::Decl { items, is_final: true, .. } = &stmt.item {
+            for item in items.iter() {
+                if let DeclItemKind::Init { ident, val } = &item.item {
+                    if val.item.is_literal() {
+                        optimizer.constants.insert(ident.clone(), val.item.clone());
+                    }
+                }
+            }
+        }
+        optimizer
+    }
+
+    fn map_boxed_statement(&mut self, stmt: &Statement<LocationMeta>) -> OptimizationResult<Box<Statement<LocationMeta>>> {
+        Ok(Box::new(self.map_statement(stmt)?))
+    }
+
+    fn map_boxed_expression(&mut self, expr: &Expression<LocationMeta>) -> OptimizationResult<Box<Expression<LocationMeta>>> {
+        Ok(Box::new(self.map_expression(expr)?))
+    }
+
+    /// identifiers of the variables declared by the statement, visible until the end of enclosing block
+    fn get_declared_variables(stmt: &Statement<LocationMeta>) -> Vec<String> {
+        if let StatementKind::Decl { items, .. } = &stmt.item {
+            items.iter()
+                .map(|item| match &item.item {
+                    DeclItemKind::NoInit { ident } | DeclItemKind::Init { ident, .. } => ident.clone(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// value of the unary operation on a literal, if it can be evaluated during compilation
+    fn fold_unary(op: &UnaryOperator, arg: &ExpressionKind<LocationMeta>) -> Option<ExpressionKind<LocationMeta>> {
+        match (op, arg) {
+            (UnaryOperator::Neg, ExpressionKind::LitInt { val }) => Some(ExpressionKind::LitInt { val: val.wrapping_neg() }),
+            (UnaryOperator::Neg, ExpressionKind::LitLong { val }) => Some(ExpressionKind::LitLong { val: val.wrapping_neg() }),
+            (UnaryOperator::Neg, ExpressionKind::LitDouble { val }) => Some(ExpressionKind::LitDouble { val: -val }),
+            (UnaryOperator::Not, ExpressionKind::LitBool { val }) => Some(ExpressionKind::LitBool { val: !val }),
+            (UnaryOperator::BitNot, ExpressionKind::LitInt { val }) => Some(ExpressionKind::LitInt { val: !val }),
+            (UnaryOperator::BitNot, ExpressionKind::LitLong { val }) => Some(ExpressionKind::LitLong { val: !val }),
+            _ => None,
+        }
+    }
+
+    /// value of the binary operation on literals, if it can be evaluated during compilation,
+    /// the result has to be the same as the one computed at runtime (division by zero is not evaluated)
+    fn fold_binary(
+        left: &ExpressionKind<LocationMeta>, op: &BinaryOperator, right: &ExpressionKind<LocationMeta>,
+    ) -> Option<ExpressionKind<LocationMeta>> {
+        match (left, right) {
+            (ExpressionKind::LitInt { val: l }, ExpressionKind::LitInt { val: r }) => {
+                let (l, r) = (*l, *r);
+                let val = match op {
+                    BinaryOperator::Plus => l.wrapping_add(r),
+                    BinaryOperator::Minus => l.wrapping_sub(r),
+                    BinaryOperator::Times => l.wrapping_mul(r),
+                    BinaryOperator::Divide => l.checked_div(r)?,
+                    BinaryOperator::Modulo => l.checked_rem(r)?,
+                    BinaryOperator::BitAnd => l & r,
+                    BinaryOperator::BitOr => l | r,
+                    BinaryOperator::BitXor => l ^ r,
+                    // only 5 lowest bits of the shift distance are used, like in the backend
+                    BinaryOperator::ShiftLeft => l.wrapping_shl(r as u32),
+                    BinaryOperator::ShiftRight => l.wrapping_shr(r as u32),
+                    BinaryOperator::ShiftRightLogical => (l as u32).wrapping_shr(r as u32) as i32,
+                    op => return Self::fold_comparison(op, l, r),
+                };
+                Some(ExpressionKind::LitInt { val })
+            }
+            (ExpressionKind::LitLong { val: l }, ExpressionKind::LitLong { val: r }) => {
+                let (l, r) = (*l, *r);
+                let val = match op {
+                    BinaryOperator::Plus => l.wrapping_add(r),
+                    BinaryOperator::Minus => l.wrapping_sub(r),
+                    BinaryOperator::Times => l.wrapping_mul(r),
+                    BinaryOperator::Divide => l.checked_div(r)?,
+                    BinaryOperator::Modulo => l.checked_rem(r)?,
+                    BinaryOperator::BitAnd => l & r,
+                    BinaryOperator::BitOr => l | r,
+                    BinaryOperator::BitXor => l ^ r,
+                    // only 6 lowest bits of the shift distance are used, like in the backend
+                    BinaryOperator::ShiftLeft => l.wrapping_shl(r as u32),
+                    BinaryOperator::ShiftRight => l.wrapping_shr(r as u32),
+                    BinaryOperator::ShiftRightLogical => (l as u64).wrapping_shr(r as u32) as i64,
+                    op => return Self::fold_comparison(op, l, r),
+                };
+                Some(ExpressionKind::LitLong { val })
+            }
+            (ExpressionKind::LitBool { val: l }, ExpressionKind::LitBool { val: r }) => {
+                let val = match op {
+                    BinaryOperator::And => *l && *r,
+                    BinaryOperator::Or => *l || *r,
+                    BinaryOperator::Equal => l == r,
+                    BinaryOperator::NotEqual => l != r,
+                    _ => return None,
+                };
+                Some(ExpressionKind::LitBool { val })
+            }
+            (ExpressionKind::LitStr { val: l }, ExpressionKind::LitStr { val: r }) if *op == BinaryOperator::Plus => {
+                // literals are stored with quotes
+                let val = format!("{}{}", &l[..l.len() - 1], &r[1..]);
+                Some(ExpressionKind::LitStr { val })
+            }
+            _ => None,
+        }
+    }
+
+    fn fold_comparison<T: PartialOrd>(op: &BinaryOperator, l: T, r: T) -> Option<ExpressionKind<LocationMeta>> {
+        let val = match op {
+            BinaryOperator::Less => l < r,
+            BinaryOperator::LessEqual => l <= r,
+            BinaryOperator::Greater => l > r,
+            BinaryOperator::GreaterEqual => l >= r,
+            BinaryOperator::Equal => l == r,
+            BinaryOperator::NotEqual => l != r,
+            _ => return None,
+        };
+        Some(ExpressionKind::LitBool { val })
+    }
+}
+
 impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for AstOptimizer {
     fn map_var_reference(&mut self, r: &Reference<LocationMeta>) -> OptimizationResult<Reference<LocationMeta>> {
-        Ok(r.clone())
+        match &r.item {
+            ReferenceKind::Array { arr, idx } => {
+                let kind = ReferenceKind::Array { arr: arr.clone(), idx: self.map_boxed_expression(idx)? };
+                Ok(Reference::new(kind, r.get_meta().clone()))
+            }
+            _ => Ok(r.clone()),
+        }
     }
 
     fn map_func_reference(&mut self, r: &Reference<LocationMeta>) -> OptimizationResult<Reference<LocationMeta>> {
         Ok(r.clone())
     }
 
+    /// each statement is optimized in the scope of variables declared before it
     fn map_block(&mut self, block: &Block<LocationMeta>) -> OptimizationResult<Block<LocationMeta>> {
-        let mapped_stmts: Vec<_> = block.item.stmts.iter()
-            .map(|stmt| self.map_statement(stmt))
-            .filter_map(Result::ok) // no error is possible at this point
-            .filter(|stmt| stmt.item != StatementKind::Empty)
-            .map(Box::new)
-            .collect();
+        let mut optimizer = self.clone();
+        let mut mapped_stmts = Vec::new();
+        for stmt in block.item.stmts.iter() {
+            let mapped_stmt = optimizer.map_statement(stmt)?;
+            optimizer = optimizer.with_declaration(&mapped_stmt);
+            if mapped_stmt.item != StatementKind::Empty {
+                mapped_stmts.push(Box::new(mapped_stmt));
+            }
+        }
 
         let mapped_block = Block::new(
             BlockItem { stmts: mapped_stmts },
@@ -32,60 +202,237 @@ impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for AstO
     }
 
     fn map_expression(&mut self, expr: &Expression<LocationMeta>) -> OptimizationResult<Expression<LocationMeta>> {
-        Ok(expr.clone()) // TODO: More optimization is possible, we can recursively check if there are constants
+        let kind = match &expr.item {
+            ExpressionKind::Reference { r } => match &r.item {
+                ReferenceKind::Ident { ident } if self.constants.contains_key(ident) => {
+                    self.constants.get(ident).unwrap().clone()
+                }
+                _ => ExpressionKind::Reference { r: self.map_var_reference(r)? },
+            },
+            ExpressionKind::Unary { op, arg } => {
+                let mapped_arg = self.map_boxed_expression(arg)?;
+                match Self::fold_unary(op, &mapped_arg.item) {
+                    Some(kind) => kind,
+                    None => ExpressionKind::Unary { op: op.clone(), arg: mapped_arg },
+                }
+            }
+            ExpressionKind::Binary { left, op, right } => {
+                let mapped_left = self.map_boxed_expression(left)?;
+                let mapped_right = self.map_boxed_expression(right)?;
+                match Self::fold_binary(&mapped_left.item, op, &mapped_right.item) {
+                    Some(kind) => kind,
+                    None => ExpressionKind::Binary { left: mapped_left, op: op.clone(), right: mapped_right },
+                }
+            }
+            ExpressionKind::App { r, args } => ExpressionKind::App {
+                r: self.map_func_reference(r)?,
+                args: args.iter().map(|expr| self.map_boxed_expression(expr)).collect::<OptimizationResult<_>>()?,
+            },
+            ExpressionKind::Interpolation { parts, exprs } => ExpressionKind::Interpolation {
+                parts: parts.clone(),
+                exprs: exprs.iter().map(|expr| self.map_boxed_expression(expr)).collect::<OptimizationResult<_>>()?,
+            },
+            ExpressionKind::InitArr { t, size } => ExpressionKind::InitArr {
+                t: t.clone(),
+                size: self.map_boxed_expression(size)?,
+            },
+            ExpressionKind::LitArr { t, items } => ExpressionKind::LitArr {
+                t: t.clone(),
+                items: items.iter().map(|expr| self.map_boxed_expression(expr)).collect::<OptimizationResult<_>>()?,
+            },
+            ExpressionKind::Mut { r, op, prefix } => ExpressionKind::Mut {
+                r: self.map_var_reference(r)?,
+                op: op.clone(),
+                prefix: *prefix,
+            },
+            ExpressionKind::Cond { cond, expr_true, expr_false } => ExpressionKind::Cond {
+                cond: self.map_boxed_expression(cond)?,
+                expr_true: self.map_boxed_expression(expr_true)?,
+                expr_false: self.map_boxed_expression(expr_false)?,
+            },
+            ExpressionKind::Cast { t, expr } => ExpressionKind::Cast {
+                t: t.clone(),
+                expr: self.map_boxed_expression(expr)?,
+            },
+            kind => kind.clone(),
+        };
+        Ok(Expression::new(kind, expr.get_meta().clone()))
     }
 
     fn map_statement(&mut self, stmt: &Statement<LocationMeta>) -> OptimizationResult<Statement<LocationMeta>> {
-        match &stmt.item {
-            StatementKind::Block { block } => {
-                let mapped_block = self.map_block(block)?;
-                let mapped_stmt = Statement::new(
-                    StatementKind::Block { block: mapped_block },
-                    stmt.get_meta().clone(),
-                );
-                Ok(mapped_stmt)
+        let kind = match &stmt.item {
+            StatementKind::Block { block } => StatementKind::Block {
+                block: self.map_block(block)?
+            },
+            StatementKind::Decl { t, items, is_final } => {
+                let mut mapped_items = Vec::new();
+                for item in items.iter() {
+                    let mut mapped_item = item.clone();
+                    if let DeclItemKind::Init { ident, val } = &item.item {
+                        mapped_item.item = DeclItemKind::Init { ident: ident.clone(), val: self.map_boxed_expression(val)? };
+                    }
+                    mapped_items.push(mapped_item);
+                }
+                StatementKind::Decl { t: t.clone(), items: mapped_items, is_final: *is_final }
             }
+            StatementKind::Ass { r, expr } => StatementKind::Ass {
+                r: self.map_var_reference(r)?,
+                expr: self.map_boxed_expression(expr)?,
+            },
+            StatementKind::Mut { r, op } => StatementKind::Mut {
+                r: self.map_var_reference(r)?,
+                op: op.clone(),
+            },
+            StatementKind::AssOp { r, op, expr } => StatementKind::AssOp {
+                r: self.map_var_reference(r)?,
+                op: op.clone(),
+                expr: self.map_boxed_expression(expr)?,
+            },
+            StatementKind::Return { expr } => StatementKind::Return {
+                expr: expr.as_ref().map(|expr| self.map_boxed_expression(expr)).transpose()?,
+            },
             StatementKind::Cond { expr, stmt: cond_stmt } => {
-                match &expr.item {
+                let mapped_expr = self.map_boxed_expression(expr)?;
+                match &mapped_expr.item {
                     ExpressionKind::LitBool { val: true } => {
-                        Ok(*cond_stmt.clone())
+                        return self.map_statement(cond_stmt);
                     }
                     ExpressionKind::LitBool { val: false } => {
                         let empty = Statement::new(
                             StatementKind::Empty,
                             cond_stmt.get_meta().clone(),
                         );
-                        Ok(empty)
+                        return Ok(empty);
                     }
-                    _ => Ok(stmt.clone())
+                    _ => StatementKind::Cond { expr: mapped_expr, stmt: self.map_boxed_statement(cond_stmt)? }
                 }
             }
             StatementKind::CondElse { expr, stmt_true, stmt_false } => {
-                match &expr.item {
+                let mapped_expr = self.map_boxed_expression(expr)?;
+                match &mapped_expr.item {
                     ExpressionKind::LitBool { val: true } => {
-                        Ok(*stmt_true.clone())
+                        return self.map_statement(stmt_true);
                     }
                     ExpressionKind::LitBool { val: false } => {
-                        Ok(*stmt_false.clone())
+                        return self.map_statement(stmt_false);
+                    }
+                    _ => StatementKind::CondElse {
+                        expr: mapped_expr,
+                        stmt_true: self.map_boxed_statement(stmt_true)?,
+                        stmt_false: self.map_boxed_statement(stmt_false)?,
                     }
-                    _ => Ok(stmt.clone())
                 }
             }
-            _ => Ok(stmt.clone())
-        }
+            StatementKind::While { label, expr, stmt } => StatementKind::While {
+                label: label.clone(),
+                expr: self.map_boxed_expression(expr)?,
+                stmt: self.map_boxed_statement(stmt)?,
+            },
+            StatementKind::For { label, t, ident, arr, stmt } => StatementKind::For {
+                label: label.clone(),
+                t: t.clone(),
+                ident: ident.clone(),
+                arr: self.map_boxed_expression(arr)?,
+                stmt: self.with_variables(vec![ident].into_iter()).map_boxed_statement(stmt)?,
+            },
+            StatementKind::ForLoop { label, init, cond, step, stmt } => {
+                // variables declared in the init statement are visible in the rest of the loop
+                let mapped_init = init.as_ref().map(|init| self.map_boxed_statement(init)).transpose()?;
+                let mut optimizer = mapped_init.as_ref()
+                    .map_or(self.clone(), |init| self.with_declaration(init));
+                StatementKind::ForLoop {
+                    label: label.clone(),
+                    init: mapped_init,
+                    cond: cond.as_ref().map(|cond| optimizer.map_boxed_expression(cond)).transpose()?,
+                    step: step.as_ref().map(|step| optimizer.map_boxed_statement(step)).transpose()?,
+                    stmt: optimizer.map_boxed_statement(stmt)?,
+                }
+            }
+            StatementKind::DoWhile { label, stmt, expr } => StatementKind::DoWhile {
+                label: label.clone(),
+                stmt: self.map_boxed_statement(stmt)?,
+                expr: self.map_boxed_expression(expr)?,
+            },
+            StatementKind::Switch { expr, cases, default } => {
+                let mut mapped_cases = Vec::new();
+                for case in cases.iter() {
+                    let mut mapped_case = case.clone();
+                    mapped_case.item.block = self.map_block(&case.item.block)?;
+                    mapped_cases.push(mapped_case);
+                }
+                StatementKind::Switch {
+                    expr: self.map_boxed_expression(expr)?,
+                    cases: mapped_cases,
+                    default: default.as_ref().map(|block| self.map_block(block)).transpose()?,
+                }
+            }
+            StatementKind::Expr { expr } => StatementKind::Expr {
+                expr: self.map_boxed_expression(expr)?,
+            },
+            StatementKind::Throw { expr } => StatementKind::Throw {
+                expr: self.map_boxed_expression(expr)?,
+            },
+            StatementKind::Assert { expr, message, location } => StatementKind::Assert {
+                expr: self.map_boxed_expression(expr)?,
+                message: message.as_ref().map(|message| self.map_boxed_expression(message)).transpose()?,
+                location: location.clone(),
+            },
+            StatementKind::Try { block, catches, finally } => {
+                let mut mapped_catches = Vec::new();
+                for catch in catches.iter() {
+                    let mut mapped_catch = catch.clone();
+                    mapped_catch.item.block = self.with_variables(vec![&catch.item.ident].into_iter())
+                        .map_block(&catch.item.block)?;
+                    mapped_catches.push(mapped_catch);
+                }
+                StatementKind::Try {
+                    block: self.map_block(block)?,
+                    catches: mapped_catches,
+                    finally: finally.as_ref().map(|block| self.map_block(block)).transpose()?,
+                }
+            }
+            kind => kind.clone(),
+        };
+        Ok(Statement::new(kind, stmt.get_meta().clone()))
     }
 
+    /// instance variables (own and inherited) shadow constants in all methods of the class,
+    /// their initializers are evaluated like the ones of global constants
     fn map_class(&mut self, class: &Class<LocationMeta>) -> OptimizationResult<Class<LocationMeta>> {
+        let fields = self.class_fields.get(class.get_key()).cloned().unwrap_or_default();
+        let mut optimizer = self.with_variables(fields.iter());
         let mut mapped_class = class.clone();
-        mapped_class.item.methods = class.item.methods.iter()
-            .map(|(k, v)| (k.clone(), self.map_function(v).unwrap())).collect();
+        for (var_name, var) in class.item.vars.iter() {
+            if let Some(val) = &var.item.val {
+                let mut mapped_var = var.clone();
+                mapped_var.item.val = Some(self.map_boxed_expression(val)?);
+                mapped_class.item.vars.insert(var_name.clone(), mapped_var);
+            }
+        }
+        for (method_name, method) in class.item.methods.iter() {
+            mapped_class.item.methods.insert(method_name.clone(), optimizer.map_function(method)?);
+        }
         Ok(mapped_class)
     }
 
     fn map_function(&mut self, function: &Function<LocationMeta>) -> OptimizationResult<Function<LocationMeta>> {
-        let mapped_block = self.map_block(&function.item.block)?;
+        let args = function.item.args.iter().map(|arg| &arg.item.ident);
+        let mapped_block = self.with_variables(args).map_block(&function.item.block)?;
         let mut mapped_function = function.clone();
         mapped_function.item.block = mapped_block;
         Ok(mapped_function)
     }
+
+    /// constants are evaluated in the order of declaration, each of them can use the previous ones
+    fn map_global(&mut self, global: &Global<LocationMeta>) -> OptimizationResult<Global<LocationMeta>> {
+        let mut mapped_global = global.clone();
+        if let DeclItemKind::Init { ident, val } = &global.item.decl {
+            let mapped_val = self.map_boxed_expression(val)?;
+            if global.item.is_const && mapped_val.item.is_literal() {
+                self.constants.insert(ident.clone(), mapped_val.item.clone());
+            }
+            mapped_global.item.decl = DeclItemKind::Init { ident: ident.clone(), val: mapped_val };
+        }
+        Ok(mapped_global)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::frontend::ast::{Block, BlockItem, Class, ClassItem, Expression, ExpressionKind, Function, FunctionItem, Reference, ReferenceKind, Statement, StatementKind};
+use crate::frontend::ast::{Block, BlockItem, Class, Expression, Function, Global, Reference, Statement, StatementKind};
 use crate::frontend::error::{FrontendError, FrontendErrorKind};
 use crate::meta::{LocationMeta, Meta};
 use crate::util::mapper::AstMapper;
@@ -7,6 +7,32 @@ pub struct BlockOrganizer;
 
 type OrganizerResult<T> = Result<T, Vec<FrontendError<LocationMeta>>>;
 
+impl BlockOrganizer {
+    /// checks if the block contains unlabeled break statement that exits the enclosing switch,
+    /// in such case the switch cannot be treated as a statement that always returns
+    fn breaks_switch(block: &Block<LocationMeta>) -> bool {
+        block.item.stmts.iter().any(|stmt| Self::statement_breaks_switch(stmt))
+    }
+
+    fn statement_breaks_switch(stmt: &Statement<LocationMeta>) -> bool {
+        match &stmt.item {
+            StatementKind::Break { label: None } => true,
+            StatementKind::Block { block } => Self::breaks_switch(block),
+            StatementKind::Cond { expr: _, stmt } => Self::statement_breaks_switch(stmt),
+            StatementKind::CondElse { expr: _, stmt_true, stmt_false } => {
+                Self::statement_breaks_switch(stmt_true) || Self::statement_breaks_switch(stmt_false)
+            }
+            StatementKind::Try { block, catches, finally } => {
+                Self::breaks_switch(block)
+                    || catches.iter().any(|catch| Self::breaks_switch(&catch.item.block))
+                    || finally.as_ref().is_some_and(Self::breaks_switch)
+            }
+            // nested loops and switches are exited by break statements inside them
+            _ => false,
+        }
+    }
+}
+
 impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for BlockOrganizer {
     fn map_var_reference(&mut self, r: &Reference<LocationMeta>) -> OrganizerResult<Reference<LocationMeta>> {
         Ok(r.clone())
@@ -18,11 +44,11 @@ impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for Bloc
 
     /// ensures there is a return value at the end of every possible path through the block
     fn map_block(&mut self, block: &Block<LocationMeta>) -> OrganizerResult<Block<LocationMeta>> {
-        // filter statements to remove anything after return statement
+        // filter statements to remove anything after return (or throw) statement
         // Iterator::take_while doesn't yield the return statement :c
         let mut filtered_stmts = Vec::new();
         for stmt in block.item.stmts.iter() {
-            if let StatementKind::Return { expr: _ } = stmt.item {
+            if let StatementKind::Return { .. } | StatementKind::Throw { .. } = stmt.item {
                 filtered_stmts.push(stmt.clone());
                 break;
             } else {
@@ -55,7 +81,7 @@ impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for Bloc
     /// ensures the statement always ends with a return value
     fn map_statement(&mut self, stmt: &Statement<LocationMeta>) -> OrganizerResult<Statement<LocationMeta>> {
         match &stmt.item {
-            StatementKind::Return { .. } => Ok(stmt.clone()),
+            StatementKind::Return { .. } | StatementKind::Throw { .. } => Ok(stmt.clone()),
             StatementKind::CondElse { expr, stmt_true, stmt_false } => {
                 let mapped_true = self.map_statement(&stmt_true)?;
                 let mapped_false = self.map_statement(&stmt_false)?;
@@ -72,6 +98,38 @@ impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for Bloc
                 };
                 Ok(Statement::new(mapped_block, stmt.get_meta().clone()))
             }
+            StatementKind::Switch { expr, cases, default: Some(default) }
+            if !cases.iter().any(|case| Self::breaks_switch(&case.item.block)) && !Self::breaks_switch(default) => {
+                // switch with default branch is handled just like CondElse: every branch has to return
+                let mut mapped_cases = Vec::new();
+                for case in cases.iter() {
+                    let mut mapped_case = case.clone();
+                    mapped_case.item.block = self.map_block(&case.item.block)?;
+                    mapped_cases.push(mapped_case);
+                }
+                let kind = StatementKind::Switch {
+                    expr: expr.clone(),
+                    cases: mapped_cases,
+                    default: Some(self.map_block(default)?),
+                };
+                Ok(Statement::new(kind, stmt.get_meta().clone()))
+            }
+            StatementKind::Try { block, catches, finally } => {
+                // try returns if the protected block and all catch blocks return,
+                // finally block is executed on the way out, so it doesn't need to return
+                let mut mapped_catches = Vec::new();
+                for catch in catches.iter() {
+                    let mut mapped_catch = catch.clone();
+                    mapped_catch.item.block = self.map_block(&catch.item.block)?;
+                    mapped_catches.push(mapped_catch);
+                }
+                let kind = StatementKind::Try {
+                    block: self.map_block(block)?,
+                    catches: mapped_catches,
+                    finally: finally.clone(),
+                };
+                Ok(Statement::new(kind, stmt.get_meta().clone()))
+            }
             s => {
                 // if the last statement is neither return, conditional nor block,
                 // we convert it to a block with itself and `return void` statement
@@ -109,4 +167,8 @@ impl AstMapper<LocationMeta, LocationMeta, FrontendError<LocationMeta>> for Bloc
         mapped_function.item.block = mapped_block;
         Ok(mapped_function)
     }
+
+    fn map_global(&mut self, global: &Global<LocationMeta>) -> OrganizerResult<Global<LocationMeta>> {
+        Ok(global.clone())
+    }
 }
@@ -1,6 +1,9 @@
+use std::fmt::Debug;
+
 use crate::frontend::ast;
 use crate::frontend::error::FrontendError;
 use crate::frontend::parser::{ParsedProgram, ParserErrors};
+use crate::frontend::preprocessor::assert_processor::AssertProcessor;
 use crate::frontend::preprocessor::ast_optimizer::AstOptimizer;
 use crate::frontend::preprocessor::block_organizer::BlockOrganizer;
 use crate::meta::LocationMeta;
@@ -8,15 +11,29 @@ use crate::util::mapper::AstMapper;
 
 pub use self::char_offset::{CharOffset, clean_comments};
 
+mod assert_processor;
 mod char_offset;
 mod ast_optimizer;
 mod block_organizer;
 
 
+/// fill in source locations (file:line:column) of assert statements, used in failure messages
+pub fn process_asserts(
+    program: ParsedProgram, locate: &dyn Fn(&LocationMeta) -> String,
+) -> Result<ParsedProgram, ParserErrors> {
+    AssertProcessor { strip_asserts: false, locate }.map_program(&program)
+}
+
+/// replace assert statements with empty statements if asserts are disabled,
+/// it is done after typechecking, so that the removed asserts are still checked
+pub fn strip_asserts<MetaT: Clone + Debug>(program: ast::Program<MetaT>) -> Result<ast::Program<MetaT>, ParserErrors> {
+    AssertProcessor { strip_asserts: true, locate: &|_| String::new() }.map_program(&program)
+}
+
 /// substitute conditional statements that are always true/false
 /// to limit number of generated conditional jump instructions
 pub fn optimize_constants(program: ParsedProgram) -> Result<ParsedProgram, ParserErrors> {
-    AstOptimizer.map_program(&program)
+    AstOptimizer::new(&program).map_program(&program)
 }
 
 /// ensure every function block ends with a return statement or conditional
@@ -0,0 +1,34 @@
+use crate::frontend::ast::{Expression, Program, Type};
+use crate::frontend::error::{FrontendError, FrontendErrorKind};
+use crate::frontend::typechecker::flow::{FlowChecker, FlowRules, FlowState, VariableUse};
+use crate::meta::LocationMeta;
+
+/// definite assignment analysis that finds reads of variables declared without initialization,
+/// which are not assigned on some execution path leading to the read
+/// (such variables hold the default value of their type)
+///
+/// objects, lists and maps declared without initialization are constructed, so they are not reported
+pub struct AssignmentRules;
+
+/// find all uses of possibly unassigned variables in the program
+pub fn check_assignments(program: &Program<LocationMeta>) -> Vec<FrontendError<LocationMeta>> {
+    FlowChecker::new(AssignmentRules).check_program(program)
+}
+
+impl FlowRules for AssignmentRules {
+    fn is_declared_with_property(
+        &self, t: &Type, val: Option<&Expression<LocationMeta>>, _state: &FlowState,
+    ) -> bool {
+        let is_constructed = matches!(t, Type::Class { .. } | Type::List { .. } | Type::Map { .. });
+        val.is_none() && !is_constructed
+    }
+
+    fn is_assigned_with_property(&self, _expr: &Expression<LocationMeta>, _state: &FlowState) -> bool {
+        false
+    }
+
+    fn check_use(&self, ident: &str, _usage: VariableUse) -> Option<FrontendErrorKind> {
+        let message = format!("Variable {} may be used before it is assigned", ident);
+        Some(FrontendErrorKind::InitializationError { message })
+    }
+}
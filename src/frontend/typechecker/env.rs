@@ -29,29 +29,3 @@ pub fn check_main(program: &Program<LocationMeta>) -> Result<(), Vec<FrontendErr
         Err(vec![FrontendError::new(kind, LocationMeta { offset: 0 })])
     }
 }
-
-/// checks if no builtin method is overwritten
-pub fn check_builtin_conflicts(
-    program: &Program<LocationMeta>, builtins: &Env<Type>,
-) -> Result<(), Vec<FrontendError<LocationMeta>>> {
-    let overwritten_builtins: Vec<_> = program.functions
-        .keys()
-        .filter(|f| builtins.contains_key(f.clone()))
-        .collect();
-    if overwritten_builtins.is_empty() {
-        Ok(())
-    } else {
-        let errors: Vec<_> = overwritten_builtins.iter()
-            .map(|f| {
-                let kind = FrontendErrorKind::EnvError {
-                    message: format!("Function {} shadows built-in function", f.clone())
-                };
-                FrontendError::new(
-                    kind,
-                    program.functions.get(f.clone()).unwrap().get_location(),
-                )
-            })
-            .collect();
-        Err(errors)
-    }
-}
@@ -0,0 +1,501 @@
+use std::collections::HashSet;
+
+use crate::frontend::ast::{BinaryOperator, Block, Class, DeclItemKind, Expression, ExpressionKind, Function, Program, Reference, ReferenceKind, Statement, StatementKind, Type};
+use crate::frontend::error::{FrontendError, FrontendErrorKind};
+use crate::meta::{GetLocation, LocationMeta};
+use crate::util::visitor::AstVisitor;
+
+/// variables that may have the analyzed property (eg. may be null) at some point of the program
+pub type FlowState = HashSet<String>;
+
+/// the way a variable is used, which may be invalid for variables with the analyzed property
+pub enum VariableUse<'a> {
+    Read,
+    FieldAccess { field: &'a String },
+    MethodCall { method: &'a String },
+    ArrayItem,
+    Iteration,
+}
+
+/// rules of a flow analysis: when variables get the analyzed property and which uses of them are invalid
+pub trait FlowRules {
+    /// checks if the variable has the property after its declaration (with optional initial value)
+    fn is_declared_with_property(
+        &self, t: &Type, val: Option<&Expression<LocationMeta>>, state: &FlowState,
+    ) -> bool;
+
+    /// checks if the variable has the property after the value of the expression is assigned to it
+    fn is_assigned_with_property(&self, expr: &Expression<LocationMeta>, state: &FlowState) -> bool;
+
+    /// variables that don't have the property when the condition is true and when it is false, respectively
+    fn get_condition_facts(&self, _expr: &Expression<LocationMeta>) -> (FlowState, FlowState) {
+        (FlowState::new(), FlowState::new())
+    }
+
+    /// get the problem caused by using the variable with the property, if this use is invalid
+    fn check_use(&self, ident: &str, usage: VariableUse) -> Option<FrontendErrorKind>;
+}
+
+/// statement that can be exited with break (and continue, if it is a loop),
+/// collects states of the program at these jumps
+struct JumpTarget {
+    label: Option<String>,
+    is_loop: bool,
+    breaks: Vec<Option<FlowState>>,
+    continues: Vec<Option<FlowState>>,
+}
+
+impl JumpTarget {
+    fn new(label: Option<String>, is_loop: bool) -> Self {
+        Self { label, is_loop, breaks: Vec::new(), continues: Vec::new() }
+    }
+}
+
+/// flow-sensitive analysis of local variables, tracking which of them may have some property
+/// on any execution path leading to the current point of the program,
+/// loops are analyzed until the states at the beginning of their iterations stop changing
+pub struct FlowChecker<RulesT: FlowRules> {
+    rules: RulesT,
+    /// variables that may have the property at current point of the program, None if the point is unreachable
+    state: Option<FlowState>,
+    /// enclosing loops and switch statements, innermost last
+    jump_targets: Vec<JumpTarget>,
+    problems: Vec<FrontendError<LocationMeta>>,
+}
+
+/// state of the program after one of the two execution paths
+fn join(state: Option<FlowState>, other: Option<FlowState>) -> Option<FlowState> {
+    match (state, other) {
+        (Some(state), Some(other)) => Some(state.union(&other).cloned().collect()),
+        (state, None) => state,
+        (None, other) => other,
+    }
+}
+
+/// identifiers of variables declared by the statement
+fn get_declared_variables(stmt: &Statement<LocationMeta>) -> Vec<String> {
+    match &stmt.item {
+        StatementKind::Decl { items, .. } => items.iter()
+            .map(|item| match &item.item {
+                DeclItemKind::NoInit { ident } | DeclItemKind::Init { ident, .. } => ident.clone(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl<RulesT: FlowRules> FlowChecker<RulesT> {
+    pub fn new(rules: RulesT) -> Self {
+        Self { rules, state: None, jump_targets: Vec::new(), problems: Vec::new() }
+    }
+
+    /// analyze all functions and methods of the program, get problems ordered by their location
+    pub fn check_program(mut self, program: &Program<LocationMeta>) -> Vec<FrontendError<LocationMeta>> {
+        for function in program.functions.values() {
+            self.visit_function(function);
+        }
+        for class in program.classes.values() {
+            self.visit_class(class);
+        }
+        self.problems.sort_by_key(|problem| problem.get_location().offset);
+        self.problems
+    }
+
+    fn has_property(&self, ident: &String) -> bool {
+        self.state.as_ref().is_some_and(|state| state.contains(ident))
+    }
+
+    fn set_property(&mut self, ident: &String, has_property: bool) {
+        if let Some(state) = &mut self.state {
+            if has_property {
+                state.insert(ident.clone());
+            } else {
+                state.remove(ident);
+            }
+        }
+    }
+
+    /// mark variables known not to have the property at the current point
+    fn clear_property(&mut self, idents: &FlowState) {
+        if let Some(state) = &mut self.state {
+            state.retain(|ident| !idents.contains(ident));
+        }
+    }
+
+    /// state of the program if the given variables didn't have the property
+    fn get_state_without(&self, idents: &FlowState) -> Option<FlowState> {
+        self.state.as_ref()
+            .map(|state| state.difference(idents).cloned().collect())
+    }
+
+    /// reports invalid use of the variable, after that the variable is assumed not to have the property
+    /// (so that the same problem is reported only once)
+    fn check_use(&mut self, ident: &String, usage: VariableUse, loc: LocationMeta) {
+        if !self.has_property(ident) {
+            return;
+        }
+        if let Some(kind) = self.rules.check_use(ident, usage) {
+            // loops are analyzed multiple times, but each problem is reported once
+            if !self.problems.iter().any(|p| p.item == kind && p.get_location() == loc) {
+                self.problems.push(FrontendError::new(kind, loc));
+            }
+            self.set_property(ident, false);
+        }
+    }
+
+    fn visit_reference(&mut self, r: &Reference<LocationMeta>, is_call: bool) {
+        match &r.item {
+            ReferenceKind::Ident { ident } => {
+                self.check_use(ident, VariableUse::Read, r.get_location());
+            }
+            ReferenceKind::Object { obj, field } => {
+                let usage = if is_call {
+                    VariableUse::MethodCall { method: field }
+                } else {
+                    VariableUse::FieldAccess { field }
+                };
+                self.check_use(obj, usage, r.get_location());
+            }
+            ReferenceKind::Array { arr, idx } => {
+                self.visit_expression(idx);
+                self.check_use(arr, VariableUse::ArrayItem, r.get_location());
+            }
+            _ => {}
+        }
+    }
+
+    /// restores the state of variables shadowed by the ones declared in a nested scope
+    fn leave_scope(&mut self, outer_state: &Option<FlowState>, idents: &[String]) {
+        for ident in idents.iter() {
+            let has_property = outer_state.as_ref().is_some_and(|state| state.contains(ident));
+            self.set_property(ident, has_property);
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block<LocationMeta>) {
+        let outer_state = self.state.clone();
+        for stmt in block.item.stmts.iter() {
+            self.visit_statement(stmt);
+        }
+        let declared: Vec<String> = block.item.stmts.iter()
+            .flat_map(|stmt| get_declared_variables(stmt))
+            .collect();
+        self.leave_scope(&outer_state, &declared);
+    }
+
+    /// analyzes the loop until the states at the beginning of its iterations stop changing,
+    /// single iteration returns the state of the program when the loop condition is false
+    fn visit_loop(
+        &mut self, label: &Option<String>,
+        iteration: &mut dyn FnMut(&mut Self) -> Option<FlowState>,
+    ) {
+        let mut entry_state = self.state.clone();
+        loop {
+            self.state = entry_state.clone();
+            self.jump_targets.push(JumpTarget::new(label.clone(), true));
+            let exit_state = iteration(self);
+            let target = self.jump_targets.pop().unwrap();
+
+            let back_edge_state = target.continues.into_iter().fold(self.state.take(), join);
+            let next_entry_state = join(entry_state.clone(), back_edge_state);
+            if next_entry_state == entry_state {
+                self.state = target.breaks.into_iter().fold(exit_state, join);
+                return;
+            }
+            entry_state = next_entry_state;
+        }
+    }
+
+    /// merges the states of continue statements of the innermost loop with the current one
+    fn join_continues(&mut self) {
+        if let Some(target) = self.jump_targets.last_mut() {
+            let continues: Vec<_> = target.continues.drain(..).collect();
+            self.state = continues.into_iter().fold(self.state.take(), join);
+        }
+    }
+
+    /// finds the target of break (any statement) or continue (only loops) with an optional label
+    fn get_jump_target(&mut self, label: &Option<String>, is_continue: bool) -> Option<&mut JumpTarget> {
+        self.jump_targets.iter_mut()
+            .rev()
+            .filter(|target| target.is_loop || (!is_continue && label.is_none()))
+            .find(|target| label.is_none() || target.label == *label)
+    }
+
+    fn visit_condition(&mut self, expr: &Expression<LocationMeta>) -> (FlowState, FlowState) {
+        self.visit_expression(expr);
+        self.rules.get_condition_facts(expr)
+    }
+
+    /// continues with the state of the loop body, returns the state when the loop is exited
+    /// (loop with condition always true can only be exited with break)
+    fn visit_loop_condition(&mut self, expr: &Expression<LocationMeta>) -> Option<FlowState> {
+        let (when_true, when_false) = self.visit_condition(expr);
+        let exit_state = match &expr.item {
+            ExpressionKind::LitBool { val: true } => None,
+            _ => self.get_state_without(&when_false),
+        };
+        self.clear_property(&when_true);
+        exit_state
+    }
+}
+
+impl<RulesT: FlowRules> AstVisitor<LocationMeta, ()> for FlowChecker<RulesT> {
+    fn visit_expression(&mut self, expr: &Expression<LocationMeta>) {
+        match &expr.item {
+            ExpressionKind::Reference { r } | ExpressionKind::Mut { r, .. } => {
+                self.visit_reference(r, false);
+            }
+            ExpressionKind::App { r, args } => {
+                for arg in args.iter() {
+                    self.visit_expression(arg);
+                }
+                self.visit_reference(r, true);
+            }
+            ExpressionKind::Unary { arg, .. } => self.visit_expression(arg),
+            ExpressionKind::Binary { left, op: BinaryOperator::And, right } => {
+                // right operand is evaluated only if the left one is true
+                let (when_true, _) = self.visit_condition(left);
+                let short_circuit_state = self.state.clone();
+                self.clear_property(&when_true);
+                self.visit_expression(right);
+                self.state = join(self.state.take(), short_circuit_state);
+            }
+            ExpressionKind::Binary { left, op: BinaryOperator::Or, right } => {
+                let (_, when_false) = self.visit_condition(left);
+                let short_circuit_state = self.state.clone();
+                self.clear_property(&when_false);
+                self.visit_expression(right);
+                self.state = join(self.state.take(), short_circuit_state);
+            }
+            ExpressionKind::Binary { left, right, .. } => {
+                self.visit_expression(left);
+                self.visit_expression(right);
+            }
+            ExpressionKind::Cond { cond, expr_true, expr_false } => {
+                let (when_true, when_false) = self.visit_condition(cond);
+                let false_state = self.get_state_without(&when_false);
+                self.clear_property(&when_true);
+                self.visit_expression(expr_true);
+                let true_state = std::mem::replace(&mut self.state, false_state);
+                self.visit_expression(expr_false);
+                self.state = join(self.state.take(), true_state);
+            }
+            ExpressionKind::Interpolation { exprs: items, .. }
+            | ExpressionKind::LitArr { items, .. } => {
+                for item in items.iter() {
+                    self.visit_expression(item);
+                }
+            }
+            ExpressionKind::InitArr { size, .. } => self.visit_expression(size),
+            _ => {}
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement<LocationMeta>) {
+        if self.state.is_none() {
+            // unreachable code is not analyzed
+            return;
+        }
+        match &stmt.item {
+            StatementKind::Block { block } => self.visit_block(block),
+            StatementKind::Decl { t, items, .. } => {
+                for item in items.iter() {
+                    match &item.item {
+                        DeclItemKind::NoInit { ident } => {
+                            let has_property = match &self.state {
+                                Some(state) => self.rules.is_declared_with_property(t, None, state),
+                                None => false,
+                            };
+                            self.set_property(ident, has_property);
+                        }
+                        DeclItemKind::Init { ident, val } => {
+                            self.visit_expression(val);
+                            let has_property = match &self.state {
+                                Some(state) => self.rules.is_declared_with_property(t, Some(val), state),
+                                None => false,
+                            };
+                            self.set_property(ident, has_property);
+                        }
+                    }
+                }
+            }
+            StatementKind::Ass { r, expr } => {
+                self.visit_expression(expr);
+                if let ReferenceKind::Ident { ident } = &r.item {
+                    let has_property = match &self.state {
+                        Some(state) => self.rules.is_assigned_with_property(expr, state),
+                        None => false,
+                    };
+                    self.set_property(ident, has_property);
+                } else {
+                    self.visit_reference(r, false);
+                }
+            }
+            StatementKind::Mut { r, .. } => self.visit_reference(r, false),
+            StatementKind::AssOp { r, expr, .. } => {
+                self.visit_expression(expr);
+                self.visit_reference(r, false);
+            }
+            StatementKind::Return { expr } => {
+                if let Some(expr) = expr {
+                    self.visit_expression(expr);
+                }
+                self.state = None;
+            }
+            StatementKind::Throw { expr } => {
+                self.visit_expression(expr);
+                self.state = None;
+            }
+            StatementKind::Cond { expr, stmt } => {
+                let (when_true, when_false) = self.visit_condition(expr);
+                let false_state = self.get_state_without(&when_false);
+                self.clear_property(&when_true);
+                self.visit_statement(stmt);
+                self.state = join(self.state.take(), false_state);
+            }
+            StatementKind::CondElse { expr, stmt_true, stmt_false } => {
+                let (when_true, when_false) = self.visit_condition(expr);
+                let false_state = self.get_state_without(&when_false);
+                self.clear_property(&when_true);
+                self.visit_statement(stmt_true);
+                let true_state = std::mem::replace(&mut self.state, false_state);
+                self.visit_statement(stmt_false);
+                self.state = join(self.state.take(), true_state);
+            }
+            StatementKind::While { label, expr, stmt } => {
+                self.visit_loop(label, &mut |checker| {
+                    let exit_state = checker.visit_loop_condition(expr);
+                    checker.visit_statement(stmt);
+                    exit_state
+                });
+            }
+            StatementKind::DoWhile { label, stmt, expr } => {
+                self.visit_loop(label, &mut |checker| {
+                    checker.visit_statement(stmt);
+                    checker.join_continues();
+                    checker.visit_loop_condition(expr)
+                });
+            }
+            StatementKind::For { label, ident, arr, stmt, .. } => {
+                // iterating over the array variable reads its items, other expressions are only evaluated
+                match &arr.item {
+                    ExpressionKind::Reference { r } => match &r.item {
+                        ReferenceKind::Ident { ident: arr_ident } => {
+                            self.check_use(arr_ident, VariableUse::Iteration, r.get_location());
+                        }
+                        _ => self.visit_expression(arr),
+                    },
+                    _ => self.visit_expression(arr),
+                }
+                let outer_state = self.state.clone();
+                self.visit_loop(label, &mut |checker| {
+                    let exit_state = checker.state.clone();
+                    checker.set_property(ident, false);
+                    checker.visit_statement(stmt);
+                    exit_state
+                });
+                self.leave_scope(&outer_state, std::slice::from_ref(ident));
+            }
+            StatementKind::ForLoop { label, init, cond, step, stmt } => {
+                let outer_state = self.state.clone();
+                let declared = match init {
+                    Some(init) => {
+                        self.visit_statement(init);
+                        get_declared_variables(init)
+                    }
+                    None => Vec::new(),
+                };
+                self.visit_loop(label, &mut |checker| {
+                    // loop without a condition can only be exited with break
+                    let exit_state = match cond {
+                        Some(cond) => checker.visit_loop_condition(cond),
+                        None => None,
+                    };
+                    checker.visit_statement(stmt);
+                    checker.join_continues();
+                    if let Some(step) = step {
+                        checker.visit_statement(step);
+                    }
+                    exit_state
+                });
+                self.leave_scope(&outer_state, &declared);
+            }
+            StatementKind::Switch { expr, cases, default } => {
+                self.visit_expression(expr);
+                let entry_state = self.state.clone();
+                self.jump_targets.push(JumpTarget::new(None, false));
+                // without default, none of the cases may be executed
+                let mut exit_state = if default.is_none() { entry_state.clone() } else { None };
+                for block in cases.iter().map(|case| &case.item.block).chain(default.iter()) {
+                    self.state = entry_state.clone();
+                    self.visit_block(block);
+                    exit_state = join(exit_state, self.state.take());
+                }
+                let target = self.jump_targets.pop().unwrap();
+                self.state = target.breaks.into_iter().fold(exit_state, join);
+            }
+            StatementKind::Break { label } => {
+                let state = self.state.take();
+                if let Some(target) = self.get_jump_target(label, false) {
+                    target.breaks.push(state);
+                }
+            }
+            StatementKind::Continue { label } => {
+                let state = self.state.take();
+                if let Some(target) = self.get_jump_target(label, true) {
+                    target.continues.push(state);
+                }
+            }
+            StatementKind::Expr { expr } => self.visit_expression(expr),
+            StatementKind::Assert { expr, message, .. } => {
+                // the program continues only if the assertion holds
+                let (when_true, _) = self.visit_condition(expr);
+                if let Some(message) = message {
+                    self.visit_expression(message);
+                }
+                self.clear_property(&when_true);
+            }
+            StatementKind::Try { block, catches, finally } => {
+                // exception can be thrown at any point of the try block, approximated by its beginning and end
+                let entry_state = self.state.clone();
+                self.visit_block(block);
+                let mut exit_state = self.state.take();
+                let catch_state = join(entry_state, exit_state.clone());
+                for catch in catches.iter() {
+                    self.state = catch_state.clone();
+                    self.set_property(&catch.item.ident, false);
+                    self.visit_block(&catch.item.block);
+                    self.leave_scope(&catch_state, std::slice::from_ref(&catch.item.ident));
+                    exit_state = join(exit_state, self.state.take());
+                }
+                if let Some(finally) = finally {
+                    // finally block is executed also when the exception is propagated,
+                    // but only its normal completion continues after the try statement
+                    self.state = join(exit_state.clone(), catch_state);
+                    self.visit_block(finally);
+                    self.state = exit_state;
+                    if self.state.is_some() {
+                        self.visit_block(finally);
+                    }
+                } else {
+                    self.state = exit_state;
+                }
+            }
+            StatementKind::Empty | StatementKind::Error => {}
+        }
+    }
+
+    fn visit_class(&mut self, class: &Class<LocationMeta>) {
+        for method in class.item.methods.values() {
+            self.visit_function(method);
+        }
+    }
+
+    /// arguments of the function never have the analyzed property
+    fn visit_function(&mut self, function: &Function<LocationMeta>) {
+        self.state = Some(FlowState::new());
+        self.jump_targets.clear();
+        self.visit_block(&function.item.block);
+    }
+}
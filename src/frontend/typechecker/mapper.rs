@@ -42,6 +42,16 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                         };
                         Err(vec![FrontendError::new(kind, loc.clone())])
                     }
+                } else if *var_t == Type::Str {
+                    // the same applies to string length, strings don't have other fields
+                    if field == "length" {
+                        Ok((ReferenceKind::StrLen { ident: obj.clone(), self_cls: self.get_self_class(obj) }, Type::Int))
+                    } else {
+                        let kind = FrontendErrorKind::EnvError {
+                            message: format!("Invalid instance variable for string: {}", field)
+                        };
+                        Err(vec![FrontendError::new(kind, loc.clone())])
+                    }
                 } else {
                     // interpret var_t as object and try to get the `field` instance variable
                     let cls = self.get_class(var_t, loc)?;
@@ -99,6 +109,24 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                         };
                         Err(vec![FrontendError::new(kind, loc.clone())])
                     }
+                } else if *var_t == Type::Str {
+                    // indexing a string returns its character
+                    let mapped_expr = self.map_expression(idx)?;
+                    let mapped_t = &mapped_expr.get_meta().t;
+                    if *mapped_t == Type::Int {
+                        let str_char_reference = ReferenceKind::StrChar {
+                            ident: arr.clone(),
+                            idx: Box::new(mapped_expr),
+                            self_cls: self.get_self_class(arr),
+                        };
+                        Ok((str_char_reference, Type::Char))
+                    } else {
+                        let kind = FrontendErrorKind::TypeError {
+                            expected: Type::Int,
+                            actual: mapped_t.clone(),
+                        };
+                        Err(vec![FrontendError::new(kind, loc.clone())])
+                    }
                 } else {
                     let kind = FrontendErrorKind::TypeError {
                         expected: Type::Array { item_t: Box::new(Type::Any) },
@@ -123,30 +151,54 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
         let typecheck_result = match &r.item {
             ReferenceKind::Ident { ident } => {
                 let func_t = self.get_func(ident, loc)?;
-                Ok((ReferenceKind::Ident { ident: ident.clone() }, func_t.clone()))
+                // backend has to call the runtime even if the linked program defines a function with the same name
+                if self.is_builtin_function(ident) {
+                    Ok((ReferenceKind::Builtin { ident: ident.clone() }, func_t.clone()))
+                } else {
+                    Ok((ReferenceKind::Ident { ident: ident.clone() }, func_t.clone()))
+                }
             }
             ReferenceKind::Object { obj, field } => {
                 let var_t = self.get_variable(obj, loc)?;
-                let cls = self.get_class(var_t, loc)?;
-                let method_t = self.get_method(cls, field, loc)?;
-
-                // similarly to the variable case, if we refer to member function of a class member
-                // we need to provide additional information about self class for the compiler
-                if self.is_class_variable(obj) {
-                    let typed_self_reference = ReferenceKind::TypedMemberObject {
-                        self_cls: self.get_current_class().unwrap().item.get_key().clone(),
-                        obj: obj.clone(),
-                        obj_class: cls.item.get_key().clone(),
-                        field: field.clone(),
+                if let Type::List { item_t } = var_t {
+                    // list and map methods are builtin, backend compiles them to runtime calls
+                    let method_t = self.get_list_method(item_t, field, loc)?;
+                    let list_reference = ReferenceKind::ListMethod {
+                        ident: obj.clone(),
+                        method: field.clone(),
+                        self_cls: self.get_self_class(obj),
                     };
-                    Ok((typed_self_reference, method_t.clone()))
-                } else {
-                    let typed_reference = ReferenceKind::TypedObject {
-                        obj: obj.clone(),
-                        cls: cls.item.get_key().clone(),
-                        field: field.clone()
+                    Ok((list_reference, method_t))
+                } else if let Type::Map { key_t, val_t } = var_t {
+                    let method_t = self.get_map_method(key_t, val_t, field, loc)?;
+                    let map_reference = ReferenceKind::MapMethod {
+                        ident: obj.clone(),
+                        method: field.clone(),
+                        self_cls: self.get_self_class(obj),
                     };
-                    Ok((typed_reference, method_t.clone()))
+                    Ok((map_reference, method_t))
+                } else {
+                    let cls = self.get_class(var_t, loc)?;
+                    let method_t = self.get_method(cls, field, loc)?;
+
+                    // similarly to the variable case, if we refer to member function of a class member
+                    // we need to provide additional information about self class for the compiler
+                    if self.is_class_variable(obj) {
+                        let typed_self_reference = ReferenceKind::TypedMemberObject {
+                            self_cls: self.get_current_class().unwrap().item.get_key().clone(),
+                            obj: obj.clone(),
+                            obj_class: cls.item.get_key().clone(),
+                            field: field.clone(),
+                        };
+                        Ok((typed_self_reference, method_t.clone()))
+                    } else {
+                        let typed_reference = ReferenceKind::TypedObject {
+                            obj: obj.clone(),
+                            cls: cls.item.get_key().clone(),
+                            field: field.clone()
+                        };
+                        Ok((typed_reference, method_t.clone()))
+                    }
                 }
             }
             ReferenceKind::ObjectSelf { field } => {
@@ -216,9 +268,38 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
             ExpressionKind::LitBool { val } => {
                 Ok((ExpressionKind::LitBool { val: val.clone() }, Type::Bool))
             }
+            ExpressionKind::LitChar { val } => {
+                Ok((ExpressionKind::LitChar { val: *val }, Type::Char))
+            }
+            ExpressionKind::LitLong { val } => {
+                Ok((ExpressionKind::LitLong { val: *val }, Type::Long))
+            }
+            ExpressionKind::LitDouble { val } => {
+                Ok((ExpressionKind::LitDouble { val: *val }, Type::Double))
+            }
             ExpressionKind::LitStr { val } => {
                 Ok((ExpressionKind::LitStr { val: val.clone() }, Type::Str))
             }
+            ExpressionKind::Interpolation { parts, exprs } => {
+                let mut mapped_exprs = Vec::new();
+                let mut errors: Vec<FrontendError<LocationMeta>> = Vec::new();
+                for expr in exprs.iter() {
+                    match self.map_expression(expr) {
+                        Ok(mapped_expr) => {
+                            if let Err(mut e) = self.check_printable_type(&mapped_expr.get_type(), &expr.get_location()) {
+                                errors.append(&mut e);
+                            }
+                            mapped_exprs.push(Box::new(mapped_expr));
+                        }
+                        Err(mut e) => errors.append(&mut e),
+                    }
+                }
+                if errors.is_empty() {
+                    Ok((ExpressionKind::Interpolation { parts: parts.clone(), exprs: mapped_exprs }, Type::Str))
+                } else {
+                    Err(errors)
+                }
+            }
             ExpressionKind::LitNull => {
                 Ok((ExpressionKind::LitNull, Type::Null))
             }
@@ -264,6 +345,14 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                             Err(errors)
                         }
                     }
+                    Type::VariadicFunction { args: exp_args, ret, formatted } => {
+                        let mapped_args = self.map_variadic_args(r, exp_args, *formatted, args)?
+                            .into_iter()
+                            .map(Box::new)
+                            .collect();
+                        let t = *ret.clone();
+                        Ok((ExpressionKind::App { r: mapped_r, args: mapped_args }, t))
+                    }
                     t => {
                         let kind = FrontendErrorKind::TypeError {
                             expected: Type::Function { args: vec![], ret: Box::new(Type::Any) },
@@ -274,12 +363,14 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                 }
             }
             ExpressionKind::Unary { op, arg } => {
+                let mapped_arg = self.map_expression(arg)?;
+                let t = mapped_arg.get_type();
                 let op_t = match op {
-                    UnaryOperator::Neg => Type::Int,
+                    UnaryOperator::Neg if t.is_numeric() => t.clone(),
+                    UnaryOperator::BitNot if t.is_integral() => t.clone(),
+                    UnaryOperator::Neg | UnaryOperator::BitNot => Type::Int,
                     UnaryOperator::Not => Type::Bool,
                 };
-                let mapped_arg = self.map_expression(&arg)?;
-                let t = mapped_arg.get_type();
                 if t == op_t {
                     Ok((ExpressionKind::Unary { op: op.clone(), arg: Box::new(mapped_arg) }, t))
                 } else {
@@ -290,6 +381,64 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                     Err(vec![FrontendError::new(kind, arg.get_location())])
                 }
             }
+            ExpressionKind::Mut { r, op, prefix } => {
+                let mapped_ref = self.map_var_reference(r)?;
+                self.check_assignable(&mapped_ref, r.get_meta())?;
+                match mapped_ref.get_type() {
+                    t if t.is_integral() => {
+                        let kind = ExpressionKind::Mut {
+                            r: mapped_ref,
+                            op: op.clone(),
+                            prefix: *prefix,
+                        };
+                        Ok((kind, t))
+                    }
+                    t => {
+                        let kind = FrontendErrorKind::TypeError {
+                            expected: Type::Int,
+                            actual: t,
+                        };
+                        Err(vec![FrontendError::new(kind, r.get_location())])
+                    }
+                }
+            }
+            ExpressionKind::Cond { cond, expr_true, expr_false } => {
+                let mapped_cond = self.map_expression(cond)?;
+                if mapped_cond.get_type() != Type::Bool {
+                    let kind = FrontendErrorKind::TypeError {
+                        expected: Type::Bool,
+                        actual: mapped_cond.get_type(),
+                    };
+                    return Err(vec![FrontendError::new(kind, cond.get_location())]);
+                }
+
+                let mapped_true = self.map_expression(expr_true)?;
+                let mapped_false = self.map_expression(expr_false)?;
+                let true_t = mapped_true.get_type();
+                let false_t = mapped_false.get_type();
+                // null in one of the branches takes the type of the other branch
+                let result_t = match (&true_t, &false_t) {
+                    (Type::Null, Type::Class { .. })
+                    | (Type::Null, Type::Array { .. }) => Some(false_t.clone()),
+                    (Type::Class { .. }, Type::Null)
+                    | (Type::Array { .. }, Type::Null) => Some(true_t.clone()),
+                    _ => self.get_types_lca(&true_t, &false_t),
+                };
+                if let Some(t) = result_t {
+                    let kind = ExpressionKind::Cond {
+                        cond: Box::new(mapped_cond),
+                        expr_true: Box::new(mapped_true),
+                        expr_false: Box::new(mapped_false),
+                    };
+                    Ok((kind, t))
+                } else {
+                    let kind = FrontendErrorKind::TypeError {
+                        expected: true_t,
+                        actual: false_t,
+                    };
+                    Err(vec![FrontendError::new(kind, expr_false.get_location())])
+                }
+            }
             ExpressionKind::Binary { left, op, right } => {
                 // TODO: Collect errors from both sides before terminating
                 let mapped_l = self.map_expression(&left)?;
@@ -302,7 +451,17 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                             Option::Some(Type::Bool)
                         }
                         BinaryOperator::Plus => {
-                            if left_t == Type::Str || left_t == Type::Int {
+                            if left_t == Type::Str || left_t.is_numeric() {
+                                Option::Some(left_t.clone())
+                            } else {
+                                Option::None
+                            }
+                        }
+                        BinaryOperator::Minus
+                        | BinaryOperator::Times
+                        | BinaryOperator::Divide
+                        | BinaryOperator::Modulo => {
+                            if left_t.is_numeric() {
                                 Option::Some(left_t.clone())
                             } else {
                                 Option::None
@@ -319,15 +478,17 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                         | BinaryOperator::GreaterEqual
                         | BinaryOperator::LessEqual
                         | BinaryOperator::Less => {
-                            if left_t == Type::Int {
+                            // strings are ordered lexicographically, characters by their codes
+                            if left_t.is_numeric() || left_t == Type::Str || left_t == Type::Char {
                                 Option::Some(Type::Bool)
                             } else {
                                 Option::None
                             }
                         }
                         _ => {
-                            if left_t == Type::Int {
-                                Option::Some(Type::Int)
+                            // bitwise operators are defined only for integers
+                            if left_t.is_integral() {
+                                Option::Some(left_t.clone())
                             } else {
                                 Option::None
                             }
@@ -375,6 +536,40 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                     Err(vec![FrontendError::new(kind, size.get_location())])
                 }
             }
+            ExpressionKind::LitArr { t, items } => {
+                if let Type::Array { item_t } = t {
+                    let mut mapped_items = Vec::new();
+                    let mut errors: Vec<FrontendError<LocationMeta>> = Vec::new();
+                    for item in items.iter() {
+                        match self.map_expression(item) {
+                            Ok(mapped_item) => {
+                                let assignment_check = self.check_assignment(
+                                    item_t,
+                                    &mapped_item.get_meta().t,
+                                );
+                                if let Err(kind) = assignment_check {
+                                    errors.push(FrontendError::new(kind, item.get_location()));
+                                } else {
+                                    mapped_items.push(Box::new(mapped_item));
+                                }
+                            }
+                            Err(mut err_vec) => {
+                                errors.append(&mut err_vec);
+                            }
+                        }
+                    }
+                    if errors.is_empty() {
+                        Ok((ExpressionKind::LitArr { t: t.clone(), items: mapped_items }, t.clone()))
+                    } else {
+                        Err(errors)
+                    }
+                } else {
+                    let kind = FrontendErrorKind::ArgumentError {
+                        message: format!("Array literal cannot initialize value of type {:?}", t)
+                    };
+                    Err(vec![FrontendError::new(kind, expr.get_location())])
+                }
+            }
             ExpressionKind::Reference { r } => {
                 let mapped_ref = self.map_var_reference(r)?;
                 let t = mapped_ref.get_type();
@@ -419,9 +614,11 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
             StatementKind::Empty => {
                 Ok(Statement::new(StatementKind::Empty, TypeMeta { t: Type::Void }))
             }
-            StatementKind::Decl { items, t } => {
+            StatementKind::Decl { items, t, is_final } => {
                 let mut errors = Vec::new();
                 let mut mapped_declitems = Vec::new();
+                // variable declared with var gets the type of its initializer
+                let mut decl_t = t.clone();
                 for declitem in items.iter() {
                     // TODO: Refactor to separate function: map declitem?
                     match &declitem.item {
@@ -432,10 +629,17 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                                     message: format!("Duplicated declaration of {}", ident)
                                 };
                                 errors.push(FrontendError::new(err, declitem.get_location()));
+                            } else if *is_final {
+                                // final variable could only hold the default value
+                                let err = FrontendErrorKind::EnvError {
+                                    message: format!("Final variable {} has to be initialized", ident)
+                                };
+                                errors.push(FrontendError::new(err, declitem.get_location()));
                             } else {
                                 // define the variable
                                 self.local_env.insert(ident.clone(), t.clone());
                                 self.local_decl.insert(ident.clone());
+                                self.local_final.remove(ident);
 
                                 let kind = DeclItemKind::NoInit { ident: ident.clone() };
                                 mapped_declitems.push(DeclItem::new(kind, TypeMeta { t: t.clone() }))
@@ -457,17 +661,28 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                             match self.map_expression(&val) {
                                 Ok(mapped_expr) => {
                                     let expr_t = &mapped_expr.get_meta().t;
-                                    match self.check_assignment(&t, expr_t) {
-                                        Ok(_) => {
-                                            self.local_env.insert(ident.clone(), t.clone());
+                                    let check_result = if *t == Type::Infer {
+                                        self.infer_variable_type(ident, expr_t)
+                                    } else {
+                                        self.check_assignment(t, expr_t).map(|_| t.clone())
+                                    };
+                                    match check_result {
+                                        Ok(item_t) => {
+                                            self.local_env.insert(ident.clone(), item_t.clone());
+                                            if *is_final {
+                                                self.local_final.insert(ident.clone());
+                                            } else {
+                                                self.local_final.remove(ident);
+                                            }
                                             let kind = DeclItemKind::Init {
                                                 ident: ident.clone(),
                                                 val: Box::new(mapped_expr.clone()),
                                             };
                                             mapped_declitems.push(DeclItem::new(
                                                 kind,
-                                                TypeMeta { t: t.clone() },
+                                                TypeMeta { t: item_t.clone() },
                                             ));
+                                            decl_t = item_t;
                                         }
                                         Err(kind) => {
                                             errors.push(FrontendError::new(kind, loc));
@@ -482,7 +697,7 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                     };
                 }
                 if errors.is_empty() {
-                    let kind = StatementKind::Decl { t: t.clone(), items: mapped_declitems };
+                    let kind = StatementKind::Decl { t: decl_t, items: mapped_declitems, is_final: *is_final };
                     Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
                 } else {
                     Err(errors)
@@ -493,6 +708,7 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                 // TODO: Collect errors from both expression and the reference before failing
                 let mapped_expr = self.map_expression(&expr)?;
                 let mapped_ref = self.map_var_reference(&r)?;
+                self.check_assignable(&mapped_ref, r.get_meta())?;
 
                 let ref_t = &mapped_ref.get_meta().t;
                 let expr_t = &mapped_expr.get_meta().t;
@@ -510,11 +726,12 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
             }
             StatementKind::Mut { r, op } => {
                 let mapped_ref = self.map_var_reference(r)?;
+                self.check_assignable(&mapped_ref, r.get_meta())?;
                 let target_t = &mapped_ref.get_meta().t;
 
                 // ++ and -- expressions can only be performed on integer types
                 match target_t {
-                    Type::Int => {
+                    t if t.is_integral() => {
                         // ++ and -- are not expressions, they don't have a return value
                         let kind = StatementKind::Mut { r: mapped_ref, op: op.clone() };
                         let meta = TypeMeta { t: Type::Void };
@@ -529,6 +746,36 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                     }
                 }
             }
+            StatementKind::AssOp { r, op, expr } => {
+                let mapped_expr = self.map_expression(expr)?;
+                let mapped_ref = self.map_var_reference(r)?;
+                self.check_assignable(&mapped_ref, r.get_meta())?;
+
+                // string concatenation is the only compound assignment allowed for non-numeric types
+                let ref_t = mapped_ref.get_type();
+                let expected_t = match (&ref_t, op) {
+                    (Type::Str, BinaryOperator::Plus) => Type::Str,
+                    (t, _) if t.is_numeric() => t.clone(),
+                    _ => Type::Int,
+                };
+                if ref_t != expected_t {
+                    let kind = FrontendErrorKind::TypeError { expected: expected_t, actual: ref_t };
+                    Err(vec![FrontendError::new(kind, r.get_location())])
+                } else if mapped_expr.get_type() != expected_t {
+                    let kind = FrontendErrorKind::TypeError {
+                        expected: expected_t,
+                        actual: mapped_expr.get_type(),
+                    };
+                    Err(vec![FrontendError::new(kind, expr.get_location())])
+                } else {
+                    let kind = StatementKind::AssOp {
+                        r: mapped_ref,
+                        op: op.clone(),
+                        expr: Box::new(mapped_expr),
+                    };
+                    Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
+                }
+            }
             StatementKind::Return { expr } => {
                 match expr {
                     None => {
@@ -614,32 +861,39 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                     }
                 }
             }
-            StatementKind::While { expr, stmt } => {
+            StatementKind::While { label, expr, stmt } => {
                 let mapped_expr = self.map_expression(&expr)?;
-                let mut typechecker = self.with_nested_env(Env::new());
+                let mut typechecker = self.with_loop(Env::new(), label, stmt.get_meta())?;
                 let mapped_stmt = typechecker.map_statement(&stmt)?;
                 let kind = StatementKind::While {
+                    label: label.clone(),
                     expr: Box::new(mapped_expr),
                     stmt: Box::new(mapped_stmt),
                 };
                 Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
             }
-            StatementKind::For { t, ident, arr, stmt } => {
+            StatementKind::For { label, t, ident, arr, stmt } => {
                 let mapped_arr = self.map_expression(&arr)?;
                 let arr_t = &mapped_arr.get_meta().t;
-                // check if arr is an array
+                // check if arr is an array or a list, iterating over a map visits its keys
                 match arr_t {
-                    Type::Array { item_t } => {
-                        // check array item type
-                        match self.check_assignment(&t, &item_t) {
-                            Ok(_) => {
+                    Type::Array { item_t } | Type::List { item_t } | Type::Map { key_t: item_t, .. } => {
+                        // check array item type, loop variable declared with var gets the item type
+                        let check_result = if *t == Type::Infer {
+                            Ok(item_t.as_ref().clone())
+                        } else {
+                            self.check_assignment(t, item_t).map(|_| t.clone())
+                        };
+                        match check_result {
+                            Ok(t) => {
                                 // check loop statement with nested environemnt
                                 let mut loop_env = Env::new();
                                 loop_env.insert(ident.clone(), t.clone());
-                                let mut typechecker = self.with_nested_env(loop_env);
+                                let mut typechecker = self.with_loop(loop_env, label, stmt.get_meta())?;
                                 let mapped_stmt = typechecker.map_statement(&stmt)?;
 
                                 let kind = StatementKind::For {
+                                    label: label.clone(),
                                     t: t.clone(),
                                     ident: ident.clone(),
                                     arr: Box::new(mapped_arr),
@@ -653,19 +907,347 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
                         }
                     }
                     invalid_arr_t => {
+                        let item_t = if *t == Type::Infer { Type::Any } else { t.clone() };
                         let kind = FrontendErrorKind::TypeError {
-                            expected: Type::Array { item_t: Box::new(t.clone()) },
+                            expected: Type::Array { item_t: Box::new(item_t) },
                             actual: invalid_arr_t.clone(),
                         };
                         Err(vec![FrontendError::new(kind, arr.get_location())])
                     }
                 }
             }
+            StatementKind::ForLoop { label, init, cond, step, stmt } => {
+                // variables declared in the initializer are visible only inside the loop
+                let mut typechecker = self.with_loop(Env::new(), label, stmt.get_meta())?;
+                let mapped_init = match init {
+                    Some(init_stmt) => Some(Box::new(typechecker.map_statement(init_stmt)?)),
+                    None => None,
+                };
+                let mapped_cond = match cond {
+                    Some(cond_expr) => {
+                        let mapped_cond = typechecker.map_expression(cond_expr)?;
+                        if mapped_cond.get_type() != Type::Bool {
+                            let kind = FrontendErrorKind::TypeError {
+                                expected: Type::Bool,
+                                actual: mapped_cond.get_type(),
+                            };
+                            return Err(vec![FrontendError::new(kind, cond_expr.get_location())]);
+                        }
+                        Some(Box::new(mapped_cond))
+                    }
+                    None => None,
+                };
+                let mapped_step = match step {
+                    Some(step_stmt) => Some(Box::new(typechecker.map_statement(step_stmt)?)),
+                    None => None,
+                };
+                let mapped_stmt = typechecker.with_nested_env(Env::new()).map_statement(stmt)?;
+                let kind = StatementKind::ForLoop {
+                    label: label.clone(),
+                    init: mapped_init,
+                    cond: mapped_cond,
+                    step: mapped_step,
+                    stmt: Box::new(mapped_stmt),
+                };
+                Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
+            }
+            StatementKind::DoWhile { label, stmt, expr } => {
+                // variables declared in the body are not visible in the condition
+                let mut typechecker = self.with_loop(Env::new(), label, stmt.get_meta())?;
+                let mapped_stmt = typechecker.map_statement(stmt)?;
+                let mapped_expr = self.map_expression(expr)?;
+                if mapped_expr.get_type() != Type::Bool {
+                    let kind = FrontendErrorKind::TypeError {
+                        expected: Type::Bool,
+                        actual: mapped_expr.get_type(),
+                    };
+                    return Err(vec![FrontendError::new(kind, expr.get_location())]);
+                }
+                let kind = StatementKind::DoWhile {
+                    label: label.clone(),
+                    stmt: Box::new(mapped_stmt),
+                    expr: Box::new(mapped_expr),
+                };
+                Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
+            }
+            StatementKind::Switch { expr, cases, default } => {
+                let mapped_expr = self.map_expression(expr)?;
+                let switch_t = mapped_expr.get_type();
+                if switch_t != Type::Int && switch_t != Type::Str && switch_t != Type::Char {
+                    let kind = FrontendErrorKind::TypeError {
+                        expected: Type::Int,
+                        actual: switch_t,
+                    };
+                    return Err(vec![FrontendError::new(kind, expr.get_location())]);
+                }
+
+                let mut errors = Vec::new();
+                let mut mapped_cases = Vec::new();
+                let mut case_values = Vec::new();
+                let mut branch_types = Vec::new();
+                for case in cases.iter() {
+                    let mut mapped_vals = Vec::new();
+                    for val in case.item.vals.iter() {
+                        match self.map_expression(val) {
+                            Ok(mapped_val) => {
+                                // case values are literals, so duplicates can be detected by comparing them
+                                // (strings are compared after decoding their escape sequences)
+                                let case_value = match &mapped_val.item {
+                                    ExpressionKind::LitStr { val } => {
+                                        let decoded = String::from_utf8_lossy(&unescape_string_literal(val)).into_owned();
+                                        ExpressionKind::LitStr { val: format!("{:?}", decoded) }
+                                    }
+                                    other => other.clone(),
+                                };
+                                if mapped_val.get_type() != switch_t {
+                                    let kind = FrontendErrorKind::TypeError {
+                                        expected: switch_t.clone(),
+                                        actual: mapped_val.get_type(),
+                                    };
+                                    errors.push(FrontendError::new(kind, val.get_location()));
+                                } else if case_values.contains(&case_value) {
+                                    let case_val = match &case_value {
+                                        ExpressionKind::LitInt { val } => val.to_string(),
+                                        ExpressionKind::LitStr { val } => val.clone(),
+                                        ExpressionKind::LitChar { val } => format!("{:?}", *val as char),
+                                        other => format!("{:?}", other),
+                                    };
+                                    let kind = FrontendErrorKind::EnvError {
+                                        message: format!("Duplicate case value: {}", case_val)
+                                    };
+                                    errors.push(FrontendError::new(kind, val.get_location()));
+                                } else {
+                                    case_values.push(case_value);
+                                    mapped_vals.push(Box::new(mapped_val));
+                                }
+                            }
+                            Err(mut v) => {
+                                errors.append(&mut v);
+                            }
+                        }
+                    }
+
+                    // every case has its own scope, just like a block statement
+                    let mut typechecker = self.with_nested_env(Env::new());
+                    typechecker.in_switch = true;
+                    match typechecker.map_block(&case.item.block) {
+                        Ok(mapped_block) => {
+                            branch_types.push((mapped_block.get_type(), case.get_location()));
+                            let item = SwitchCaseItem { vals: mapped_vals, block: mapped_block };
+                            mapped_cases.push(SwitchCase::new(item, TypeMeta { t: Type::Void }));
+                        }
+                        Err(mut v) => {
+                            errors.append(&mut v);
+                        }
+                    }
+                }
+
+                let mapped_default = match default {
+                    Some(block) => {
+                        let mut typechecker = self.with_nested_env(Env::new());
+                        typechecker.in_switch = true;
+                        match typechecker.map_block(block) {
+                            Ok(mapped_block) => {
+                                branch_types.push((mapped_block.get_type(), block.get_location()));
+                                Some(mapped_block)
+                            }
+                            Err(mut v) => {
+                                errors.append(&mut v);
+                                None
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+
+                // similarly to CondElse, switch with default branch returns only if all branches return
+                let t = if mapped_default.is_some() {
+                    let mut common_t = branch_types[0].0.clone();
+                    for (branch_t, loc) in branch_types.iter().skip(1) {
+                        match self.get_types_lca(&common_t, branch_t) {
+                            Some(lca_t) => {
+                                common_t = lca_t;
+                            }
+                            None => {
+                                let kind = FrontendErrorKind::TypeError {
+                                    expected: common_t,
+                                    actual: branch_t.clone(),
+                                };
+                                return Err(vec![FrontendError::new(kind, loc.clone())]);
+                            }
+                        }
+                    }
+                    common_t
+                } else {
+                    Type::Void
+                };
+                let kind = StatementKind::Switch {
+                    expr: Box::new(mapped_expr),
+                    cases: mapped_cases,
+                    default: mapped_default,
+                };
+                Ok(Statement::new(kind, TypeMeta { t }))
+            }
+            StatementKind::Break { label } => {
+                self.check_loop_jump(label, false, stmt.get_meta())?;
+                let kind = StatementKind::Break { label: label.clone() };
+                Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
+            }
+            StatementKind::Continue { label } => {
+                self.check_loop_jump(label, true, stmt.get_meta())?;
+                let kind = StatementKind::Continue { label: label.clone() };
+                Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
+            }
             StatementKind::Expr { expr } => {
                 let mapped_expr = self.map_expression(&expr)?;
                 let kind = StatementKind::Expr { expr: Box::new(mapped_expr) };
                 Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
             }
+            StatementKind::Throw { expr } => {
+                // only class instances can be thrown
+                let mapped_expr = self.map_expression(expr)?;
+                self.get_class(&mapped_expr.get_type(), expr.get_meta())?;
+                let kind = StatementKind::Throw { expr: Box::new(mapped_expr) };
+                Ok(Statement::new(kind, TypeMeta { t: Type::Never }))
+            }
+            StatementKind::Assert { expr, message, location } => {
+                let mapped_expr = self.map_expression(expr)?;
+                if mapped_expr.get_type() != Type::Bool {
+                    let kind = FrontendErrorKind::TypeError {
+                        expected: Type::Bool,
+                        actual: mapped_expr.get_type(),
+                    };
+                    return Err(vec![FrontendError::new(kind, expr.get_location())]);
+                }
+
+                // optional message is evaluated only if the assertion fails
+                let mapped_message = match message {
+                    Some(message) => {
+                        let mapped_message = self.map_expression(message)?;
+                        if mapped_message.get_type() != Type::Str {
+                            let kind = FrontendErrorKind::TypeError {
+                                expected: Type::Str,
+                                actual: mapped_message.get_type(),
+                            };
+                            return Err(vec![FrontendError::new(kind, message.get_location())]);
+                        }
+                        Some(Box::new(mapped_message))
+                    }
+                    None => None,
+                };
+                let kind = StatementKind::Assert {
+                    expr: Box::new(mapped_expr),
+                    message: mapped_message,
+                    location: location.clone(),
+                };
+                Ok(Statement::new(kind, TypeMeta { t: Type::Void }))
+            }
+            StatementKind::Try { block, catches, finally } => {
+                let mut errors = Vec::new();
+                let mut typechecker = self.with_nested_env(Env::new());
+                let mapped_block = typechecker.map_block(block);
+
+                let mut mapped_catches = Vec::new();
+                let mut caught_classes: Vec<&Class<LocationMeta>> = Vec::new();
+                let mut branch_types = Vec::new();
+                for catch in catches.iter() {
+                    let catch_t = &catch.item.t;
+                    let cls = match self.get_class(catch_t, catch.get_meta()) {
+                        Ok(cls) => cls,
+                        Err(mut v) => {
+                            errors.append(&mut v);
+                            continue;
+                        }
+                    };
+
+                    // clause that catches a class (or its superclass) makes later clauses for it unreachable
+                    let caught_cls = caught_classes.iter()
+                        .find(|caught_cls| {
+                            let caught_t = Type::Class { ident: caught_cls.get_key().clone() };
+                            self.check_assignment(&caught_t, catch_t).is_ok()
+                        });
+                    if let Some(caught_cls) = caught_cls {
+                        let kind = FrontendErrorKind::EnvError {
+                            message: format!(
+                                "Exception {} is already caught by the catch clause for {}",
+                                cls.get_key(), caught_cls.get_key()
+                            )
+                        };
+                        errors.push(FrontendError::new(kind, catch.get_location()));
+                        continue;
+                    }
+                    caught_classes.push(cls);
+
+                    // caught exception is visible only inside the catch block
+                    let mut catch_env = Env::new();
+                    catch_env.insert(catch.item.ident.clone(), catch_t.clone());
+                    let mut typechecker = self.with_nested_env(catch_env);
+                    match typechecker.map_block(&catch.item.block) {
+                        Ok(mapped_block) => {
+                            branch_types.push((mapped_block.get_type(), catch.get_location()));
+                            let item = CatchClauseItem {
+                                t: catch_t.clone(),
+                                ident: catch.item.ident.clone(),
+                                block: mapped_block,
+                            };
+                            mapped_catches.push(CatchClause::new(item, TypeMeta { t: catch_t.clone() }));
+                        }
+                        Err(mut v) => {
+                            errors.append(&mut v);
+                        }
+                    }
+                }
+
+                let mapped_finally = match finally {
+                    Some(block) => {
+                        let mut typechecker = self.with_nested_env(Env::new());
+                        match typechecker.map_block(block) {
+                            Ok(mapped_block) => Some(mapped_block),
+                            Err(mut v) => {
+                                errors.append(&mut v);
+                                None
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                let mapped_block = match mapped_block {
+                    Ok(mapped_block) => mapped_block,
+                    Err(mut v) => {
+                        errors.append(&mut v);
+                        return Err(errors);
+                    }
+                };
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+
+                // similarly to CondElse, try returns only if the protected block and all catch blocks return
+                let mut common_t = mapped_block.get_type();
+                for (branch_t, loc) in branch_types.into_iter() {
+                    match self.get_types_lca(&common_t, &branch_t) {
+                        Some(lca_t) => {
+                            common_t = lca_t;
+                        }
+                        None => {
+                            let kind = FrontendErrorKind::TypeError {
+                                expected: common_t,
+                                actual: branch_t,
+                            };
+                            return Err(vec![FrontendError::new(kind, loc)]);
+                        }
+                    }
+                }
+                let kind = StatementKind::Try {
+                    block: mapped_block,
+                    catches: mapped_catches,
+                    finally: mapped_finally,
+                };
+                Ok(Statement::new(kind, TypeMeta { t: common_t }))
+            }
             StatementKind::Error => {
                 unreachable!()
             }
@@ -675,13 +1257,14 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
     fn map_class(&mut self, class: &Class<LocationMeta>) -> TypeCheckResult<Class<TypeMeta>> {
         let mut typechecker = self.with_class(class);
 
-        // variables are mapped by swapping meta, no errors can happen here
-        let mut mapped_vars: Vec<_> = class.item.vars
+        // variables are mapped by swapping meta, only their initializers have to be checked
+        let (mapped_vars, var_errors): (Vec<_>, Vec<_>) = class.item.vars
             .values()
-            .map(|cv| {
-                let meta = TypeMeta { t: cv.item.t.clone() };
-                ClassVar::new(cv.item.clone(), meta)
-            })
+            .map(|cv| self.map_class_var(cv))
+            .partition(Result::is_ok);
+        let mut mapped_vars: Vec<ClassVar<TypeMeta>> = mapped_vars
+            .into_iter()
+            .map(Result::unwrap)
             .collect();
 
         // every method needs to be checked for type correctness
@@ -695,9 +1278,10 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
             .into_iter()
             .map(Result::unwrap)
             .collect();
-        let errors: Vec<FrontendError<LocationMeta>> = errors
+        let errors: Vec<FrontendError<LocationMeta>> = var_errors
             .into_iter()
             .map(Result::unwrap_err)
+            .chain(errors.into_iter().map(Result::unwrap_err))
             .flatten()
             .collect();
 
@@ -728,16 +1312,29 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
             .collect();
 
         let mut typechecker = self.with_nested_env(function.to_type_env());
+        typechecker.local_final = function.item.args.iter()
+            .filter(|arg| arg.item.is_final)
+            .map(|arg| arg.item.ident.clone())
+            .collect();
         let mapped_block = typechecker.map_block(&function.item.block)?;
 
-        match self.check_assignment(&function.item.ret, &mapped_block.get_meta().t) {
+        // extern functions have no body to check, only their signature
+        let check_result = if function.item.is_extern {
+            self.check_extern_signature(&function.item)
+        } else {
+            self.check_assignment(&function.item.ret, &mapped_block.get_meta().t)
+        };
+        match check_result {
             Ok(_) => {
-                if let Ok(item) = FunctionItem::new(
+                if let Ok(mut item) = FunctionItem::new(
                     function.item.ret.clone(),
                     function.item.ident.clone(),
                     mapped_args,
                     mapped_block,
                 ) {
+                    if function.item.is_extern {
+                        item = item.as_extern();
+                    }
                     Ok(Function::new(item.clone(), TypeMeta { t: item.get_type() }))
                 } else {
                     // because we only transformed metadata in envs, we know creation cannot fail
@@ -749,4 +1346,163 @@ impl AstMapper<LocationMeta, TypeMeta, FrontendError<LocationMeta>> for TypeChec
             }
         }
     }
+
+    /// globals are checked in an empty environment, where only the previously declared globals are visible,
+    /// constants have to be initialized with a literal (after the constant expressions are evaluated by the optimizer)
+    fn map_global(&mut self, global: &Global<LocationMeta>) -> TypeCheckResult<Global<TypeMeta>> {
+        let t = &global.item.t;
+        let ident = global.item.get_key();
+        let mapped_decl = match &global.item.decl {
+            DeclItemKind::Init { val, .. } if !global.item.is_const || val.item.is_literal() => {
+                let mapped_val = self.with_globals_before(ident).map_expression(val)?;
+                if let Err(kind) = self.check_assignment(t, &mapped_val.get_type()) {
+                    return Err(vec![FrontendError::new(kind, val.get_location())]);
+                }
+                DeclItemKind::Init { ident: ident.clone(), val: Box::new(mapped_val) }
+            }
+            DeclItemKind::NoInit { .. } if !global.item.is_const => {
+                DeclItemKind::NoInit { ident: ident.clone() }
+            }
+            _ => {
+                let kind = FrontendErrorKind::EnvError {
+                    message: format!("Constant {} has to be initialized with a constant expression", ident)
+                };
+                return Err(vec![FrontendError::new(kind, global.get_location())]);
+            }
+        };
+        let item = GlobalItem::new(t.clone(), mapped_decl, global.item.is_const);
+        Ok(Global::new(item, TypeMeta { t: t.clone() }))
+    }
+}
+
+impl TypeChecker<'_> {
+    /// instance variables can be initialized with a literal (after the constant expressions are evaluated
+    /// by the optimizer), which is checked in an empty environment, final ones have to be initialized
+    fn map_class_var(&mut self, var: &ClassVar<LocationMeta>) -> TypeCheckResult<ClassVar<TypeMeta>> {
+        let t = &var.item.t;
+        let ident = var.item.get_key();
+        let mapped_val = match &var.item.val {
+            Some(val) if val.item.is_literal() => {
+                let mapped_val = self.with_clean_env().map_expression(val)?;
+                if let Err(kind) = self.check_assignment(t, &mapped_val.get_type()) {
+                    return Err(vec![FrontendError::new(kind, val.get_location())]);
+                }
+                Some(Box::new(mapped_val))
+            }
+            None if !var.item.is_final => None,
+            val => {
+                let message = if val.is_some() {
+                    format!("Field {} has to be initialized with a constant expression", ident)
+                } else {
+                    format!("Final field {} has to be initialized", ident)
+                };
+                let kind = FrontendErrorKind::EnvError { message };
+                return Err(vec![FrontendError::new(kind, var.get_location())]);
+            }
+        };
+        let item = ClassVarItem { t: t.clone(), ident: ident.clone(), is_final: var.item.is_final, val: mapped_val };
+        Ok(ClassVar::new(item, TypeMeta { t: t.clone() }))
+    }
+
+    /// fixed arguments of a variadic builtin are checked like in regular calls, the remaining ones
+    /// have to be printable and match conversions in the format string (for formatted builtins)
+    fn map_variadic_args(
+        &mut self, r: &Reference<LocationMeta>, exp_args: &[Box<Type>], formatted: bool,
+        args: &[Box<Expression<LocationMeta>>],
+    ) -> TypeCheckResult<Vec<Expression<TypeMeta>>> {
+        if args.len() < exp_args.len() {
+            let kind = FrontendErrorKind::ArgumentError {
+                message: format!(
+                    "Incorrect argument count, expected at least {} got {}",
+                    exp_args.len(),
+                    args.len()
+                )
+            };
+            return Err(vec![FrontendError::new(kind, r.get_location())]);
+        }
+
+        let mut mapped_args = Vec::new();
+        let mut errors: Vec<FrontendError<LocationMeta>> = Vec::new();
+        for (idx, arg_expr) in args.iter().enumerate() {
+            match self.map_expression(arg_expr) {
+                Ok(mapped_arg) => {
+                    let arg_check = match exp_args.get(idx) {
+                        Some(expected_arg_type) => self.check_assignment(expected_arg_type, &mapped_arg.get_type())
+                            .map_err(|kind| vec![FrontendError::new(kind, arg_expr.get_location())]),
+                        None => self.check_printable_type(&mapped_arg.get_type(), &arg_expr.get_location()),
+                    };
+                    match arg_check {
+                        Ok(()) => mapped_args.push(mapped_arg),
+                        Err(mut err_vec) => errors.append(&mut err_vec),
+                    }
+                }
+                Err(mut err_vec) => {
+                    errors.append(&mut err_vec);
+                }
+            }
+        }
+
+        if formatted && errors.is_empty() {
+            let format_idx = exp_args.len() - 1;
+            let values: Vec<_> = args[format_idx + 1..].iter()
+                .map(|arg| arg.as_ref())
+                .zip(mapped_args[format_idx + 1..].iter())
+                .collect();
+            if let Err(mut err_vec) = self.check_format_args(&args[format_idx], values) {
+                errors.append(&mut err_vec);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(mapped_args)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// format has to be a string literal, so that its conversions are checked against the values
+    /// (passed both before and after typechecking) during compilation
+    fn check_format_args(
+        &self, format: &Expression<LocationMeta>,
+        values: Vec<(&Expression<LocationMeta>, &Expression<TypeMeta>)>,
+    ) -> Result<(), Vec<FrontendError<LocationMeta>>> {
+        let specs = match &format.item {
+            ExpressionKind::LitStr { val } => parse_format_string(val).map(|(_, specs)| specs),
+            _ => Err(String::from("Format has to be a string literal")),
+        };
+        let specs = match specs {
+            Ok(specs) => specs,
+            Err(message) => {
+                let kind = FrontendErrorKind::ArgumentError { message };
+                return Err(vec![FrontendError::new(kind, format.get_location())]);
+            }
+        };
+        if specs.len() != values.len() {
+            let kind = FrontendErrorKind::ArgumentError {
+                message: format!(
+                    "Format string expects {} values, got {}",
+                    specs.len(),
+                    values.len()
+                )
+            };
+            return Err(vec![FrontendError::new(kind, format.get_location())]);
+        }
+
+        let errors: Vec<_> = specs.iter()
+            .zip(values.iter())
+            .filter(|(spec, (_, mapped_value))| !spec.accepts(&mapped_value.get_type()))
+            .map(|(spec, (value, mapped_value))| {
+                let kind = FrontendErrorKind::TypeError {
+                    expected: spec.expected_type(),
+                    actual: mapped_value.get_type(),
+                };
+                FrontendError::new(kind, value.get_location())
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
@@ -1,28 +1,70 @@
+use crate::frontend::{CheckLevel, FrontendOptions};
 use crate::frontend::ast::Program;
+use crate::frontend::error::FrontendError;
 use crate::meta::{LocationMeta, TypeMeta};
 use crate::util::mapper::AstMapper;
 
-use self::env::check_builtin_conflicts;
+use self::assignment_checker::check_assignments;
 use self::mapper::TypeCheckResult;
+use self::null_checker::check_nulls;
 use self::typechecker::TypeChecker;
 use self::util::get_builtins;
 
 mod util;
 mod mapper;
 mod env;
+mod flow;
+mod null_checker;
+mod assignment_checker;
 mod typechecker;
 
 
 /// main typechecker function: checks types of the entire program,
 /// converts implicit self-references to current object into explicit ones,
 /// converts all references to object members to the typed ones,
-/// converts array.length to the dedicated ArrayLength reference
-pub fn check_types(program: Program<LocationMeta>) -> TypeCheckResult<Program<TypeMeta>> {
-    // get builtin functions and check for duplicate declarations
+/// converts array.length and string.length to the dedicated length references
+///
+/// functions and classes of the already checked library can be used in the program (unless it defines
+/// its own ones with the same names), but they are not checked again and are not a part of the result
+///
+/// optional analyses of the correct program are reported as warnings or errors, depending on options
+pub fn check_types(
+    program: Program<LocationMeta>, library: &Program<LocationMeta>, options: &FrontendOptions,
+) -> TypeCheckResult<(Program<TypeMeta>, Vec<FrontendError<LocationMeta>>)> {
+    // get builtin functions, functions of the program can shadow them
     let buitlins = get_builtins();
-    check_builtin_conflicts(&program, &buitlins)?;
 
     // create typechecker and iterate over entire program (classes & functions)
-    let mut typechecker = TypeChecker::new(&program, &buitlins);
-    typechecker.map_program(&program)
+    let mut declarations = program.clone();
+    declarations.link(library);
+    let mut typechecker = TypeChecker::new(&declarations, &buitlins);
+    let checked_program = typechecker.map_program(&program)?;
+
+    // optional analyses are performed only on the correctly typed program
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    if options.null_checks != CheckLevel::Disabled {
+        report_problems(options.null_checks, check_nulls(&program), &mut warnings, &mut errors);
+    }
+    if options.initialization_checks != CheckLevel::Disabled {
+        report_problems(options.initialization_checks, check_assignments(&program), &mut warnings, &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok((checked_program, warnings))
+    } else {
+        Err(errors)
+    }
+}
+
+/// adds problems found by an optional analysis to warnings or errors, depending on its check level
+fn report_problems(
+    level: CheckLevel, problems: Vec<FrontendError<LocationMeta>>,
+    warnings: &mut Vec<FrontendError<LocationMeta>>, errors: &mut Vec<FrontendError<LocationMeta>>,
+) {
+    match level {
+        CheckLevel::Disabled => {}
+        CheckLevel::Warning => warnings.extend(problems),
+        CheckLevel::Error => errors.extend(problems),
+    }
 }
@@ -0,0 +1,106 @@
+use crate::frontend::ast::{BinaryOperator, Expression, ExpressionKind, Program, ReferenceKind, Type, UnaryOperator};
+use crate::frontend::error::{FrontendError, FrontendErrorKind};
+use crate::frontend::typechecker::flow::{FlowChecker, FlowRules, FlowState, VariableUse};
+use crate::meta::LocationMeta;
+
+/// flow-sensitive analysis that finds dereferences of variables which may be null,
+/// variables become possibly null when assigned null (or declared as arrays without initialization)
+/// and become non-null when checked with `x != (T)null` or `x == (T)null` in a condition
+pub struct NullRules;
+
+/// find all dereferences of possibly null values in the program
+pub fn check_nulls(program: &Program<LocationMeta>) -> Vec<FrontendError<LocationMeta>> {
+    FlowChecker::new(NullRules).check_program(program)
+}
+
+/// null literal (possibly cast to a class or array type)
+fn is_null_literal(expr: &Expression<LocationMeta>) -> bool {
+    matches!(&expr.item, ExpressionKind::LitNull | ExpressionKind::Cast { .. })
+}
+
+/// checks if the expression may evaluate to null
+fn is_null_expression(expr: &Expression<LocationMeta>, state: &FlowState) -> bool {
+    match &expr.item {
+        ExpressionKind::LitNull | ExpressionKind::Cast { .. } => true,
+        ExpressionKind::Reference { r } => match &r.item {
+            ReferenceKind::Ident { ident } => state.contains(ident),
+            _ => false,
+        },
+        ExpressionKind::Cond { expr_true, expr_false, .. } => {
+            is_null_expression(expr_true, state) || is_null_expression(expr_false, state)
+        }
+        _ => false,
+    }
+}
+
+impl FlowRules for NullRules {
+    fn is_declared_with_property(
+        &self, t: &Type, val: Option<&Expression<LocationMeta>>, state: &FlowState,
+    ) -> bool {
+        match val {
+            Some(val) => is_null_expression(val, state),
+            // arrays are the only values which are null by default
+            None => matches!(t, Type::Array { .. }),
+        }
+    }
+
+    fn is_assigned_with_property(&self, expr: &Expression<LocationMeta>, state: &FlowState) -> bool {
+        is_null_expression(expr, state)
+    }
+
+    /// variables compared with null in the condition
+    fn get_condition_facts(&self, expr: &Expression<LocationMeta>) -> (FlowState, FlowState) {
+        match &expr.item {
+            ExpressionKind::Binary { left, op, right } => match op {
+                BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                    let compared = match (&left.item, &right.item) {
+                        (ExpressionKind::Reference { r }, _) if is_null_literal(right) => Some(r),
+                        (_, ExpressionKind::Reference { r }) if is_null_literal(left) => Some(r),
+                        _ => None,
+                    };
+                    let mut facts = FlowState::new();
+                    if let Some(ReferenceKind::Ident { ident }) = compared.map(|r| &r.item) {
+                        facts.insert(ident.clone());
+                    }
+                    if *op == BinaryOperator::NotEqual {
+                        (facts, FlowState::new())
+                    } else {
+                        (FlowState::new(), facts)
+                    }
+                }
+                BinaryOperator::And => {
+                    let (left_true, _) = self.get_condition_facts(left);
+                    let (right_true, _) = self.get_condition_facts(right);
+                    (left_true.union(&right_true).cloned().collect(), FlowState::new())
+                }
+                BinaryOperator::Or => {
+                    let (_, left_false) = self.get_condition_facts(left);
+                    let (_, right_false) = self.get_condition_facts(right);
+                    (FlowState::new(), left_false.union(&right_false).cloned().collect())
+                }
+                _ => (FlowState::new(), FlowState::new()),
+            },
+            ExpressionKind::Unary { op: UnaryOperator::Not, arg } => {
+                let (when_true, when_false) = self.get_condition_facts(arg);
+                (when_false, when_true)
+            }
+            _ => (FlowState::new(), FlowState::new()),
+        }
+    }
+
+    fn check_use(&self, ident: &str, usage: VariableUse) -> Option<FrontendErrorKind> {
+        let message = match usage {
+            // passing null around is fine, only dereferences fail
+            VariableUse::Read => return None,
+            VariableUse::FieldAccess { field } => {
+                format!("Variable {} may be null when accessing its field {}", ident, field)
+            }
+            VariableUse::MethodCall { method } => {
+                format!("Variable {} may be null when calling its method {}", ident, method)
+            }
+            VariableUse::ArrayItem => format!("Array {} may be null when accessing its item", ident),
+            VariableUse::Iteration => format!("Array {} may be null when iterating over it", ident),
+        };
+        Some(FrontendErrorKind::NullError { message })
+    }
+}
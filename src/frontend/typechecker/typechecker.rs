@@ -1,9 +1,9 @@
 use std::collections::HashSet;
 
-use crate::frontend::ast::{Class, Keyed, Program, Type};
+use crate::frontend::ast::{Class, FunctionItem, Keyed, Program, Reference, ReferenceKind, Type};
 use crate::frontend::error::{FrontendError, FrontendErrorKind};
 use crate::frontend::typechecker::util::ToTypeEnv;
-use crate::meta::LocationMeta;
+use crate::meta::{LocationMeta, TypeMeta};
 use crate::util::env::Env;
 
 #[derive(Debug, PartialEq)]
@@ -24,8 +24,20 @@ pub struct TypeChecker<'prog> {
     /// set of variables declared in current block, necessary to prevent re-declaration
     pub local_decl: HashSet<String>,
 
+    /// set of local variables declared as final, which cannot be assigned
+    pub local_final: HashSet<String>,
+
     /// set of variables that belong to current class (empty if there is no class)
     pub class_env: Env<Type>,
+
+    /// labels of the loops enclosing currently checked statement (None for unlabeled loop)
+    pub loop_labels: Vec<Option<String>>,
+
+    /// true if currently checked statement is inside a switch, which can be exited with break
+    pub in_switch: bool,
+
+    /// number of global variables visible in current scope (initializers see only the previous ones)
+    pub visible_globals: usize,
 }
 
 impl<'p> TypeChecker<'p> {
@@ -36,8 +48,12 @@ impl<'p> TypeChecker<'p> {
             builtins,
             local_env: Env::new(),
             local_decl: HashSet::new(),
+            local_final: HashSet::new(),
             current_class: Option::None,
             class_env: Env::new(),
+            loop_labels: Vec::new(),
+            in_switch: false,
+            visible_globals: program.globals.len(),
         }
     }
 
@@ -46,15 +62,29 @@ impl<'p> TypeChecker<'p> {
         Self::new(self.program, self.builtins)
     }
 
+    /// creates TypeChecker with fresh environment for the initializer of a global variable,
+    /// where only the globals declared before it are visible
+    pub fn with_globals_before(&self, ident: &String) -> Self {
+        let mut new_self = self.with_clean_env();
+        new_self.visible_globals = self.program.globals.iter()
+            .position(|global| global.item.get_key() == ident)
+            .unwrap();
+        new_self
+    }
+
     /// creates TypeChecker for the same program, but specified environment
     pub fn with_env(&self, env: Env<Type>) -> Self {
         Self {
             program: self.program,
             local_env: env,
             local_decl: HashSet::new(),
+            local_final: HashSet::new(),
             builtins: self.builtins,
             current_class: self.current_class,
             class_env: Env::new(),
+            loop_labels: Vec::new(),
+            in_switch: false,
+            visible_globals: self.visible_globals,
         }
     }
 
@@ -65,18 +95,95 @@ impl<'p> TypeChecker<'p> {
 
         new_self.local_decl.clear();
         for (k, v) in nested_env.iter() {
+            // variables of the nested environment shadow outer (possibly final) ones
             new_self.local_env.insert(k.clone(), v.clone());
+            new_self.local_final.remove(k);
         }
 
         new_self
     }
 
+    /// creates TypeChecker for the body of a loop, with a nested environment
+    pub fn with_loop(&self, nested_env: Env<Type>, label: &Option<String>, loc: &LocationMeta) -> Result<Self, Vec<FrontendError<LocationMeta>>> {
+        if label.is_some() && self.loop_labels.contains(label) {
+            let kind = FrontendErrorKind::EnvError {
+                message: format!("Duplicated loop label: {}", label.as_ref().unwrap())
+            };
+            return Err(vec![FrontendError::new(kind, loc.clone())]);
+        }
+        let mut new_self = self.with_nested_env(nested_env);
+        new_self.loop_labels.push(label.clone());
+        Ok(new_self)
+    }
+
+    /// checks if break (or continue) statement with given label has a matching loop (or switch)
+    pub fn check_loop_jump(&self, label: &Option<String>, is_continue: bool, loc: &LocationMeta) -> Result<(), Vec<FrontendError<LocationMeta>>> {
+        let message = match label {
+            Some(ident) if !self.loop_labels.contains(label) => {
+                format!("Undefined loop label: {}", ident)
+            }
+            None if self.loop_labels.is_empty() && (is_continue || !self.in_switch) => {
+                let statement = if is_continue { "continue" } else { "break" };
+                format!("Statement {} used outside of a loop", statement)
+            }
+            _ => return Ok(()),
+        };
+        let kind = FrontendErrorKind::EnvError { message };
+        Err(vec![FrontendError::new(kind, loc.clone())])
+    }
+
+    /// checks if the reference can be a target of assignment, length of arrays and strings,
+    /// characters of strings, constants and final variables are read-only
+    pub fn check_assignable(&self, r: &Reference<TypeMeta>, loc: &LocationMeta) -> Result<(), Vec<FrontendError<LocationMeta>>> {
+        let message = match &r.item {
+            ReferenceKind::ArrayLen { ident } | ReferenceKind::StrLen { ident, .. } => {
+                format!("Cannot assign to length of {}", ident)
+            }
+            ReferenceKind::StrChar { ident, .. } => {
+                format!("Cannot assign to character of string {}, strings are immutable", ident)
+            }
+            ReferenceKind::Ident { ident } if self.is_global_constant(ident) => {
+                format!("Cannot assign to constant {}", ident)
+            }
+            ReferenceKind::Ident { ident } if self.local_final.contains(ident) => {
+                format!("Cannot assign to final variable {}", ident)
+            }
+            ReferenceKind::TypedObject { cls, field, .. }
+            | ReferenceKind::TypedMemberObject { obj_class: cls, field, .. } if self.is_final_field(cls, field) => {
+                // there are no constructors, so final fields can only be set by their initializers
+                format!("Cannot assign to final field {}", field)
+            }
+            _ => return Ok(()),
+        };
+        let kind = FrontendErrorKind::EnvError { message };
+        Err(vec![FrontendError::new(kind, loc.clone())])
+    }
+
+    /// gets the type of a variable declared with var from the type of its initializer,
+    /// which cannot be null or void as they don't determine the variable type
+    pub fn infer_variable_type(&self, ident: &String, t: &Type) -> Result<Type, FrontendErrorKind> {
+        let message = match t {
+            Type::Null => format!("Cannot infer type of {} from null", ident),
+            Type::Void | Type::Never => format!("Cannot infer type of {} from void expression", ident),
+            t => return Ok(t.clone()),
+        };
+        Err(FrontendErrorKind::ArgumentError { message })
+    }
+
     /// creates TypeChecker for same program and copy of current environment
     /// extended to contain all values from nested_env
     pub fn with_class(&self, cls: &'p Class<LocationMeta>) -> Self {
         let mut new_self = self.clone();
         new_self.current_class = Option::Some(cls);
         new_self.class_env = cls.to_type_env();
+        // inherited fields are also visible, unless the subclass declares a field with the same name
+        let mut current = cls.item.parent.as_ref().and_then(|parent| self.program.classes.get(parent));
+        while let Some(superclass) = current {
+            for (ident, t) in superclass.to_type_env() {
+                new_self.class_env.entry(ident).or_insert(t);
+            }
+            current = superclass.item.parent.as_ref().and_then(|parent| self.program.classes.get(parent));
+        }
         new_self
     }
 
@@ -103,7 +210,8 @@ impl<'p> TypeChecker<'p> {
 
     /// check if lvalue == rvalue or lvalue is superclass of rvalue
     pub fn check_assignment(&self, lvalue: &Type, rvalue: &Type) -> Result<(), FrontendErrorKind> {
-        if lvalue == rvalue {
+        if lvalue == rvalue || *rvalue == Type::Never {
+            // statement that always throws can end a block of any type
             Ok(())
         } else if let Some(rvalue_t) = self.get_parent(&rvalue) {
             self.check_assignment(&lvalue, &rvalue_t)
@@ -138,8 +246,10 @@ impl<'p> TypeChecker<'p> {
 
     /// get lowest common ancestor (most specific common type) for 2 types
     pub fn get_types_lca(&self, t1: &Type, t2: &Type) -> Option<Type> {
-        if t1 == t2 {
+        if t1 == t2 || *t2 == Type::Never {
             Option::Some(t1.clone())
+        } else if *t1 == Type::Never {
+            Option::Some(t2.clone())
         } else {
             let mut supertypes = HashSet::new();
             self.get_type_ancestors(t1.clone(), &mut supertypes);
@@ -152,12 +262,48 @@ impl<'p> TypeChecker<'p> {
         self.class_env.contains_key(ident) && !self.local_env.contains_key(ident)
     }
 
-    /// get variable type from local environment or class environment
+    /// checks if the field of the class (or its closest superclass declaring it) is final
+    pub fn is_final_field(&self, cls: &String, field: &String) -> bool {
+        let mut current = self.program.classes.get(cls);
+        while let Some(cls) = current {
+            if let Some(var) = cls.item.vars.get(field) {
+                return var.item.is_final;
+            }
+            current = cls.item.parent.as_ref().and_then(|parent| self.program.classes.get(parent));
+        }
+        false
+    }
+
+    /// get name of the current class if the variable refers to its member,
+    /// backend has to load such variables from self
+    pub fn get_self_class(&self, ident: &String) -> Option<String> {
+        if self.is_class_variable(ident) {
+            self.get_current_class().map(|cls| cls.item.get_key().clone())
+        } else {
+            None
+        }
+    }
+
+    /// checks if the variable refers to a global constant (not shadowed by local or class variable)
+    pub fn is_global_constant(&self, ident: &String) -> bool {
+        !self.local_env.contains_key(ident) && !self.class_env.contains_key(ident)
+            && self.program.get_global(ident).is_some_and(|global| global.item.is_const)
+    }
+
+    /// get variable type from local environment, class environment or global variables
     pub fn get_variable(&self, ident: &String, loc: &LocationMeta) -> Result<&Type, Vec<FrontendError<LocationMeta>>> {
         if let Some(t) = self.local_env.get(ident) {
             Ok(t)
         } else if let Some(t) = self.class_env.get(ident) {
             Ok(t)
+        } else if let Some(global) = self.program.globals[..self.visible_globals].iter()
+            .find(|global| global.item.get_key() == ident) {
+            Ok(&global.item.t)
+        } else if self.program.get_global(ident).is_some() {
+            let kind = FrontendErrorKind::EnvError {
+                message: format!("Global variable {} is used before its declaration", ident)
+            };
+            Err(vec![FrontendError::new(kind, loc.clone())])
         } else {
             let kind = FrontendErrorKind::EnvError {
                 message: format!("Undefined variable: {}", ident)
@@ -207,6 +353,11 @@ impl<'p> TypeChecker<'p> {
         }
     }
 
+    /// checks if the function call refers to the builtin function (not shadowed by the program)
+    pub fn is_builtin_function(&self, ident: &String) -> bool {
+        !self.program.functions.contains_key(ident) && self.builtins.contains_key(ident)
+    }
+
     /// get a type of gloablly defined or bult-in function
     pub fn get_func(&self, ident: &String, loc: &LocationMeta) -> Result<Type, Vec<FrontendError<LocationMeta>>> {
         if let Some(func) = self.program.functions.get(ident) {
@@ -241,6 +392,93 @@ impl<'p> TypeChecker<'p> {
             Err(vec![FrontendError::new(kind, loc.clone())])
         }
     }
+
+    /// check if a value of the given type can be converted to string (printed or interpolated
+    /// in a string literal), objects have to implement `string toString()` method
+    pub fn check_printable_type(&self, t: &Type, loc: &LocationMeta) -> Result<(), Vec<FrontendError<LocationMeta>>> {
+        match t {
+            Type::Int | Type::Long | Type::Char | Type::Double | Type::Bool | Type::Str => Ok(()),
+            Type::Class { .. } => {
+                let cls = self.get_class(t, loc)?;
+                let expected_t = Type::Function { args: vec![], ret: Box::new(Type::Str) };
+                let method_t = self.get_method(cls, &String::from("toString"), loc)?;
+                if method_t == expected_t {
+                    Ok(())
+                } else {
+                    let kind = FrontendErrorKind::TypeError { expected: expected_t, actual: method_t };
+                    Err(vec![FrontendError::new(kind, loc.clone())])
+                }
+            }
+            _ => {
+                let kind = FrontendErrorKind::TypeError { expected: Type::Str, actual: t.clone() };
+                Err(vec![FrontendError::new(kind, loc.clone())])
+            }
+        }
+    }
+
+    /// extern functions are called directly from C, so they can only take and return
+    /// values of primitive types and strings, which have the same representation in both languages
+    pub fn check_extern_signature(&self, function: &FunctionItem<LocationMeta>) -> Result<(), FrontendErrorKind> {
+        let is_c_type = |t: &Type| matches!(
+            t,
+            Type::Int | Type::Long | Type::Char | Type::Double | Type::Bool | Type::Str
+        );
+        let args_valid = function.args.iter().all(|arg| is_c_type(&arg.item.t));
+        if args_valid && (function.ret == Type::Void || is_c_type(&function.ret)) {
+            Ok(())
+        } else {
+            Err(FrontendErrorKind::EnvError {
+                message: format!("Extern function {} can only use primitive types and strings", function.ident)
+            })
+        }
+    }
+
+    /// get type of a builtin list method, item_t is the type of list elements
+    pub fn get_list_method(
+        &self, item_t: &Type, method: &String, loc: &LocationMeta,
+    ) -> Result<Type, Vec<FrontendError<LocationMeta>>> {
+        let item_t = Box::new(item_t.clone());
+        let int_t = Box::new(Type::Int);
+        let (args, ret) = match method.as_str() {
+            "push" => (vec![item_t], Type::Void),
+            "pop" => (vec![], *item_t),
+            "get" => (vec![int_t], *item_t),
+            "set" => (vec![int_t, item_t], Type::Void),
+            "size" => (vec![], Type::Int),
+            "insert" => (vec![int_t, item_t], Type::Void),
+            "remove" => (vec![int_t], *item_t),
+            _ => {
+                let kind = FrontendErrorKind::EnvError {
+                    message: format!("No method named {} for list", method)
+                };
+                return Err(vec![FrontendError::new(kind, loc.clone())]);
+            }
+        };
+        Ok(Type::Function { args, ret: Box::new(ret) })
+    }
+
+    /// get type of a builtin map method, key_t and val_t are type parameters of the map
+    pub fn get_map_method(
+        &self, key_t: &Type, val_t: &Type, method: &String, loc: &LocationMeta,
+    ) -> Result<Type, Vec<FrontendError<LocationMeta>>> {
+        let key_t = Box::new(key_t.clone());
+        let val_t = Box::new(val_t.clone());
+        let (args, ret) = match method.as_str() {
+            "put" => (vec![key_t, val_t], Type::Void),
+            "get" => (vec![key_t], *val_t),
+            "containsKey" => (vec![key_t], Type::Bool),
+            "remove" => (vec![key_t], Type::Void),
+            "size" => (vec![], Type::Int),
+            "keys" => (vec![], Type::List { item_t: key_t }),
+            _ => {
+                let kind = FrontendErrorKind::EnvError {
+                    message: format!("No method named {} for map", method)
+                };
+                return Err(vec![FrontendError::new(kind, loc.clone())]);
+            }
+        };
+        Ok(Type::Function { args, ret: Box::new(ret) })
+    }
 }
 
 impl Clone for TypeChecker<'_> {
@@ -251,8 +489,12 @@ impl Clone for TypeChecker<'_> {
             builtins: self.builtins,
             local_env: self.local_env.clone(),
             local_decl: self.local_decl.clone(),
+            local_final: self.local_final.clone(),
             current_class: self.current_class,
-            class_env: self.class_env.clone()
+            class_env: self.class_env.clone(),
+            loop_labels: self.loop_labels.clone(),
+            in_switch: self.in_switch,
+            visible_globals: self.visible_globals,
         }
     }
 
@@ -261,7 +503,11 @@ impl Clone for TypeChecker<'_> {
         self.builtins = source.builtins;
         self.local_env = source.local_env.clone();
         self.local_decl = source.local_decl.clone();
+        self.local_final = source.local_final.clone();
         self.current_class = source.current_class;
         self.class_env = source.class_env.clone();
+        self.loop_labels = source.loop_labels.clone();
+        self.in_switch = source.in_switch;
+        self.visible_globals = source.visible_globals;
     }
 }
@@ -3,39 +3,38 @@ use std::iter::{FromIterator, IntoIterator};
 
 use crate::frontend::ast::{Arg, Block, Class, ClassItem, Function, FunctionItem, Type};
 use crate::frontend::error::FrontendError;
+use crate::frontend::natives::get_native_functions;
 use crate::meta::{LocationMeta, Meta};
 use crate::util::env::Env;
 
 /// get environment containing all builtin functions
 pub fn get_builtins() -> Env<Type> {
-    let builtin_print_int = Type::Function {
-        args: vec![Box::new(Type::Int)],
-        ret: Box::new(Type::Void),
-    };
-    let builtin_print_string = Type::Function {
-        args: vec![Box::new(Type::Str)],
-        ret: Box::new(Type::Void),
-    };
-    let builtin_read_int = Type::Function {
+    let natives = get_native_functions()
+        .into_iter()
+        .map(|native| (native.name.clone(), native.get_type()));
+
+    // variadic functions are compiler intrinsics, their calls are translated to formatted printing
+    let builtin_print = Type::VariadicFunction {
         args: vec![],
-        ret: Box::new(Type::Int),
+        ret: Box::new(Type::Void),
+        formatted: false,
     };
-    let builtin_read_string = Type::Function {
+    let builtin_println = Type::VariadicFunction {
         args: vec![],
-        ret: Box::new(Type::Str),
+        ret: Box::new(Type::Void),
+        formatted: false,
     };
-    let builtin_error = Type::Function {
-        args: vec![],
+    let builtin_printf = Type::VariadicFunction {
+        args: vec![Box::new(Type::Str)],
         ret: Box::new(Type::Void),
+        formatted: true,
     };
-    let builtin_vec: Vec<(String, Type)> = vec![
-        (String::from("printInt"), builtin_print_int),
-        (String::from("printString"), builtin_print_string),
-        (String::from("readInt"), builtin_read_int),
-        (String::from("readString"), builtin_read_string),
-        (String::from("error"), builtin_error),
+    let intrinsics: Vec<(String, Type)> = vec![
+        (String::from("print"), builtin_print),
+        (String::from("println"), builtin_println),
+        (String::from("printf"), builtin_printf),
     ];
-    Env::from_iter(builtin_vec.into_iter())
+    Env::from_iter(natives.chain(intrinsics))
 }
 
 pub trait ToTypeEnv {
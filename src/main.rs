@@ -3,18 +3,39 @@ use std::path::Path;
 use std::process::{Command, exit, ExitStatus};
 
 use latte::backend::compile;
-use latte::frontend::CheckedProgram;
+use latte::frontend::{CheckLevel, CheckedProgram, FrontendOptions};
 use latte::frontend::process_file;
 
-/// get a single required command line argument
-pub fn parse_arg() -> String {
+/// get the required input file name, compilation options and additional LLVM files to link
+/// (.bc or .ll, for example C libraries with functions declared as extern) from command line arguments
+pub fn parse_args() -> (String, FrontendOptions, Vec<String>) {
     let args: Vec<String> = env::args().collect();
-    match args.get(1) {
-        Some(input_filename) => {
-            String::from(input_filename)
+    let mut options = FrontendOptions::default();
+    let mut input_filename = None;
+    let mut link_filenames = Vec::new();
+    for arg in args.iter().skip(1) {
+        match arg.as_str() {
+            "--strip-asserts" => options.strip_asserts = true,
+            "--null-checks" => options.null_checks = CheckLevel::Warning,
+            "--strict-null-checks" => options.null_checks = CheckLevel::Error,
+            "--no-initialization-checks" => options.initialization_checks = CheckLevel::Disabled,
+            "--strict-initialization-checks" => options.initialization_checks = CheckLevel::Error,
+            _ if arg.ends_with(".bc") || arg.ends_with(".ll") => {
+                link_filenames.push(arg.clone());
+            }
+            _ if input_filename.is_none() && !arg.starts_with("--") => {
+                input_filename = Some(arg.clone());
+            }
+            _ => {
+                println!("Unknown argument: {}", arg);
+                exit(2)
+            }
         }
+    }
+    match input_filename {
+        Some(input_filename) => (input_filename, options, link_filenames),
         None => {
-            println!("Usage: {} {}", &args[0], "[input_filename]");
+            println!("Usage: {} [--strip-asserts] [--null-checks | --strict-null-checks] [--no-initialization-checks | --strict-initialization-checks] [input_filename] [library.bc | library.ll ...]", &args[0]);
             exit(2)
         }
     }
@@ -59,7 +80,7 @@ fn compile_llvm_file(program: CheckedProgram, output_path: &String) {
 /// compile and link binary (.bc) file or exit with error
 fn compile_binary_file(
     llvm_assembler: &String, llvm_linker: &String, llvm_runtime: &String,
-    llvm_compiled_program: &String, binary_output_path: &String,
+    llvm_compiled_program: &String, binary_output_path: &String, link_filenames: &Vec<String>,
 ) {
     let mut compilation_output_dir = env::temp_dir().to_path_buf();
     compilation_output_dir.push("latte_program_out.bc");
@@ -77,12 +98,13 @@ fn compile_binary_file(
         .arg(binary_output_path)
         .arg(llvm_runtime)
         .arg(compilation_output_file)
+        .args(link_filenames)
         .status();
     check_exit_code(llvm_linker, &linking_status);
 }
 
 fn main() {
-    let input_filename = parse_arg();
+    let (input_filename, options, link_filenames) = parse_args();
     let llvm_assembler = parse_env("LLVM_ASSEMBLER", "llvm-as");
     let llvm_linker = parse_env("LLVM_LINKER", "llvm-link");
     let llvm_runtime = parse_env("LLVM_RUNTIME", "lib/runtime.bc");
@@ -94,9 +116,12 @@ fn main() {
         Path::new(&input_filename).with_extension("bc").to_str().unwrap()
     );
 
-    match process_file(input_filename) {
-        Ok(prog) => {
+    match process_file(input_filename, &options) {
+        Ok((prog, warnings)) => {
             eprintln!("OK");
+            for warning in warnings.iter() {
+                eprintln!("Warning: {}", warning);
+            }
             compile_llvm_file(prog, &llvm_output_filename);
             compile_binary_file(
                 &llvm_assembler,
@@ -104,6 +129,7 @@ fn main() {
                 &llvm_runtime,
                 &llvm_output_filename,
                 &binary_output_filename,
+                &link_filenames,
             );
         }
         Err(err_vec) => {
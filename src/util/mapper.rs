@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use crate::frontend::ast::{Block, Class, ClassItem, Expression, Function, Program, Reference, Statement};
+use crate::frontend::ast::{Block, Class, Expression, Function, Global, Program, Reference, Statement};
 
 pub trait AstMapper<FromMeta, ToMeta, ErrT> {
     /// reference to a variable or object property
@@ -14,11 +14,26 @@ pub trait AstMapper<FromMeta, ToMeta, ErrT> {
     fn map_statement(&mut self, stmt: &Statement<FromMeta>) -> Result<Statement<ToMeta>, Vec<ErrT>>;
     fn map_class(&mut self, class: &Class<FromMeta>) -> Result<Class<ToMeta>, Vec<ErrT>>;
     fn map_function(&mut self, function: &Function<FromMeta>) -> Result<Function<ToMeta>, Vec<ErrT>>;
+    fn map_global(&mut self, global: &Global<FromMeta>) -> Result<Global<ToMeta>, Vec<ErrT>>;
 
     /// main ast mapper function, default implementation
     fn map_program(
         &mut self, program: &Program<FromMeta>,
     ) -> Result<Program<ToMeta>, Vec<ErrT>> where ToMeta: Debug + Clone + Sized, ErrT: Debug {
+        // map all globals first and in order, as they can be used in functions and classes
+        let (mapped_globals, global_errors): (Vec<_>, Vec<_>) = program.globals
+            .iter()
+            .map(|global| self.map_global(global))
+            .partition(Result::is_ok);
+        let mut global_errors: Vec<ErrT> = global_errors
+            .into_iter()
+            .flat_map(Result::unwrap_err)
+            .collect();
+        let mapped_globals: Vec<Global<ToMeta>> = mapped_globals
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
         // map all functions, collect errors
         let (mut mapped_func, mut errors): (Vec<_>, Vec<_>) = program.functions
             .values()
@@ -26,8 +41,7 @@ pub trait AstMapper<FromMeta, ToMeta, ErrT> {
             .partition(Result::is_ok);
         let mut errors: Vec<ErrT> = errors
             .into_iter()
-            .map(Result::unwrap_err)
-            .flatten()
+            .flat_map(Result::unwrap_err)
             .collect();
         let mut mapped_func: Vec<Function<ToMeta>> = mapped_func
             .into_iter()
@@ -41,8 +55,7 @@ pub trait AstMapper<FromMeta, ToMeta, ErrT> {
             .partition(Result::is_ok);
         let mut cls_errors: Vec<ErrT> = cls_errors
             .into_iter()
-            .map(Result::unwrap_err)
-            .flatten()
+            .flat_map(Result::unwrap_err)
             .collect();
         let mut mapped_cls: Vec<Class<ToMeta>> = mapped_cls
             .into_iter()
@@ -50,8 +63,9 @@ pub trait AstMapper<FromMeta, ToMeta, ErrT> {
             .collect();
 
         errors.append(&mut cls_errors);
+        errors.append(&mut global_errors);
         if errors.is_empty() {
-            if let Ok(prog) = Program::new(&mut mapped_cls, &mut mapped_func) {
+            if let Ok(prog) = Program::new(&mut mapped_cls, &mut mapped_func, mapped_globals) {
                 Ok(prog)
             } else {
                 // not possible to fail program creation when re-constructed from previously valid program
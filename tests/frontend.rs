@@ -2,7 +2,7 @@ extern crate latte;
 extern crate include_dir;
 
 use include_dir::{include_dir, Dir};
-use latte::frontend::process_code;
+use latte::frontend::{CheckLevel, FrontendOptions, process_code, process_code_with_options};
 
 
 fn parse_good_dir(dir: Dir, failed_cases: &mut Vec<String>) {
@@ -23,7 +23,7 @@ fn parse_good_dir(dir: Dir, failed_cases: &mut Vec<String>) {
                 },
                 Err(e) => {
                     println!("Failed {:?}", e);
-                    failed_cases.push(String::from(file_name));
+                    failed_cases.push(file_name);
                 },
             }
         }
@@ -37,8 +37,31 @@ fn good_examples_processed() {
     let objects_dir_1: Dir = include_dir!("tests/extensions/objects1");
     let objects_dir_2: Dir = include_dir!("tests/extensions/objects2");
     let struct_dir: Dir = include_dir!("tests/extensions/struct");
+    let switch_dir: Dir = include_dir!("tests/extensions/switch");
+    let loops_dir: Dir = include_dir!("tests/extensions/loops");
+    let operators_dir: Dir = include_dir!("tests/extensions/operators");
+    let strings_dir: Dir = include_dir!("tests/extensions/strings");
+    let chars_dir: Dir = include_dir!("tests/extensions/chars");
+    let doubles_dir: Dir = include_dir!("tests/extensions/doubles");
+    let longs_dir: Dir = include_dir!("tests/extensions/longs");
+    let lists_dir: Dir = include_dir!("tests/extensions/lists");
+    let maps_dir: Dir = include_dir!("tests/extensions/maps");
+    let exceptions_dir: Dir = include_dir!("tests/extensions/exceptions");
+    let asserts_dir: Dir = include_dir!("tests/extensions/asserts");
+    let interpolation_dir: Dir = include_dir!("tests/extensions/interpolation");
+    let printing_dir: Dir = include_dir!("tests/extensions/printing");
+    let prelude_dir: Dir = include_dir!("tests/extensions/prelude");
+    let externs_dir: Dir = include_dir!("tests/extensions/externs");
+    let globals_dir: Dir = include_dir!("tests/extensions/globals");
+    let inference_dir: Dir = include_dir!("tests/extensions/inference");
+    let final_dir: Dir = include_dir!("tests/extensions/final");
     let mut failed_cases: Vec<String> = vec![];
-    for dir in vec![good_dir, arrays_dir, objects_dir_1, objects_dir_2, struct_dir] {
+    for dir in vec![
+        good_dir, arrays_dir, objects_dir_1, objects_dir_2, struct_dir,
+        switch_dir, loops_dir, operators_dir, strings_dir, chars_dir, doubles_dir, longs_dir,
+        lists_dir, maps_dir, exceptions_dir, asserts_dir, interpolation_dir,
+        printing_dir, prelude_dir, externs_dir, globals_dir, inference_dir, final_dir,
+    ] {
         parse_good_dir(dir, &mut failed_cases);
     }
     assert_eq!(failed_cases.len(), 0usize, "{:?}", failed_cases);
@@ -58,14 +81,19 @@ fn bad_exampels_failed() {
                 .contents_utf8().unwrap();
             let file_name = String::from(file_name);
             let source_code = String::from(source_code);
-            match process_code(file_name.clone(), source_code) {
-                Ok(_) => {
-                    failed_cases.push(String::from(file_name));
-                },
-                Err(_) => {
+            // disabled asserts are still typechecked, so the files have to be rejected in both modes
+            let strip_options = FrontendOptions { strip_asserts: true, ..Default::default() };
+            match (
+                process_code(file_name.clone(), source_code.clone()),
+                process_code_with_options(file_name.clone(), source_code, &strip_options),
+            ) {
+                (Err(_), Err(_)) => {
                     // make sure to run cargo test with --nocapture flag
                     println!("Passed {}", file_name);
                 },
+                _ => {
+                    failed_cases.push(file_name);
+                },
             }
         }
     }
@@ -76,3 +104,67 @@ fn bad_exampels_failed() {
         failed_cases
     );
 }
+
+/// checks that an optional analysis reports problems in all bad files (as errors in strict mode
+/// and as warnings otherwise) and doesn't report anything in good files
+fn check_optional_analysis(
+    good_dir: Dir, bad_dir: Dir, strict_options: FrontendOptions, warning_options: FrontendOptions,
+) -> Vec<String> {
+    let mut failed_cases: Vec<String> = vec![];
+    for (dir, expect_problems) in [(good_dir, false), (bad_dir, true)] {
+        for file in dir.files() {
+            if file.path().extension().unwrap() != "lat" {
+                continue;
+            }
+            let file_name = String::from(file.path().to_str().unwrap());
+            let source_code = String::from(file.contents_utf8().unwrap());
+
+            let strict_result = process_code_with_options(file_name.clone(), source_code.clone(), &strict_options);
+            let warning_result = process_code_with_options(file_name.clone(), source_code, &warning_options);
+            let passed = match (strict_result, warning_result) {
+                (Err(_), Ok((_, warnings))) => expect_problems && !warnings.is_empty(),
+                (Ok((_, strict_warnings)), Ok((_, warnings))) => {
+                    !expect_problems && strict_warnings.is_empty() && warnings.is_empty()
+                }
+                _ => false,
+            };
+            if passed {
+                println!("Passed {}", file_name);
+            } else {
+                failed_cases.push(file_name);
+            }
+        }
+    }
+    failed_cases
+}
+
+#[test]
+fn null_checks_reported() {
+    let good_dir: Dir = include_dir!("tests/nulls/good");
+    let bad_dir: Dir = include_dir!("tests/nulls/bad");
+    let options = FrontendOptions { initialization_checks: CheckLevel::Disabled, ..Default::default() };
+    let strict_options = FrontendOptions { null_checks: CheckLevel::Error, ..options.clone() };
+    let warning_options = FrontendOptions { null_checks: CheckLevel::Warning, ..options };
+    let failed_cases = check_optional_analysis(good_dir, bad_dir, strict_options, warning_options);
+    assert_eq!(
+        failed_cases.len(),
+        0usize,
+        "Null checks failed for following files: {:?}",
+        failed_cases
+    );
+}
+
+#[test]
+fn initialization_checks_reported() {
+    let good_dir: Dir = include_dir!("tests/initialization/good");
+    let bad_dir: Dir = include_dir!("tests/initialization/bad");
+    let strict_options = FrontendOptions { initialization_checks: CheckLevel::Error, ..Default::default() };
+    let warning_options = FrontendOptions { initialization_checks: CheckLevel::Warning, ..Default::default() };
+    let failed_cases = check_optional_analysis(good_dir, bad_dir, strict_options, warning_options);
+    assert_eq!(
+        failed_cases.len(),
+        0usize,
+        "Initialization checks failed for following files: {:?}",
+        failed_cases
+    );
+}